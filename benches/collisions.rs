@@ -0,0 +1,50 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use uuid::Uuid;
+use rust_game_server_practice::config::{BroadPhase, GameConfig};
+use rust_game_server_practice::game::{Bullet, GameState, Player, Team, PLAYER_RADIUS};
+use rust_game_server_practice::game::entity::EntityKind;
+use rust_game_server_practice::geometry::vector::Vector2f;
+
+/// 50 players and 500 bullets, with every bullet clear of every player so a step leaves the state as it was.
+fn crowded_state(config: &GameConfig) -> GameState {
+    let mut state = GameState::new(config);
+    let rng = fastrand::Rng::with_seed(1);
+
+    let players: Vec<Vector2f> = (0..50)
+        .map(|_| Vector2f::new(rng.f32() * config.world.width, rng.f32() * config.world.height))
+        .collect();
+
+    for position in players.iter() {
+        let id = Uuid::new_v4();
+        state.entities.insert(id, EntityKind::Player(Player::new(id, Team::Red, *position)));
+    }
+
+    let mut bullets = 0;
+
+    while bullets < 500 {
+        let position = Vector2f::new(rng.f32() * config.world.width, rng.f32() * config.world.height);
+
+        if players.iter().any(|player| player.distance(&position) < PLAYER_RADIUS * 3.0) {
+            continue;
+        }
+
+        let bullet = Bullet::new(None, position, Vector2f::new(300.0, 0.0));
+        state.entities.insert(bullet.id, EntityKind::Bullet(bullet));
+        bullets += 1;
+    }
+
+    state
+}
+
+fn collisions(c: &mut Criterion) {
+    for (name, broad_phase) in [("step_spatial_hash", BroadPhase::SpatialHash), ("step_quadtree", BroadPhase::QuadTree)] {
+        let config = GameConfig { health_packs: 0, speed_boosts: 0, broad_phase, ..GameConfig::default() };
+        let mut state = crowded_state(&config);
+
+        // A zero delta keeps everything in place, so each iteration runs the same collision pass.
+        c.bench_function(name, |b| b.iter(|| state.step(0.0, &config)));
+    }
+}
+
+criterion_group!(benches, collisions);
+criterion_main!(benches);
@@ -0,0 +1,88 @@
+use std::sync::{Mutex, RwLock};
+use std::thread;
+use criterion::{criterion_group, criterion_main, Criterion};
+use uuid::Uuid;
+use rust_game_server_practice::config::GameConfig;
+use rust_game_server_practice::game::{Bullet, GameState, Player, Team};
+use rust_game_server_practice::game::entity::EntityKind;
+use rust_game_server_practice::geometry::vector::Vector2f;
+
+const SESSIONS: usize = 50;
+const TICKS: usize = 10;
+
+/// A state holding 200 entities, half players and half bullets.
+fn crowded_state() -> GameState {
+    let config = GameConfig { health_packs: 0, speed_boosts: 0, ..GameConfig::default() };
+    let mut state = GameState::new(&config);
+
+    for i in 0..100 {
+        let position = Vector2f::new((i * 8) as f32, (i * 6) as f32);
+        let id = Uuid::new_v4();
+        state.entities.insert(id, EntityKind::Player(Player::new(id, Team::Red, position)));
+
+        let bullet = Bullet::new(Some(id), position, Vector2f::new(300.0, 0.0));
+        state.entities.insert(bullet.id, EntityKind::Bullet(bullet));
+    }
+
+    state
+}
+
+/// The two ways the game can guard its state: the old `Mutex` and the `RwLock` that replaced it.
+trait StateLock: Sync {
+    fn read_with<R>(&self, f: impl FnOnce(&GameState) -> R) -> R;
+
+    fn write_with(&self, f: impl FnOnce(&mut GameState));
+}
+
+impl StateLock for Mutex<GameState> {
+    fn read_with<R>(&self, f: impl FnOnce(&GameState) -> R) -> R {
+        f(&self.lock().unwrap())
+    }
+
+    fn write_with(&self, f: impl FnOnce(&mut GameState)) {
+        f(&mut self.lock().unwrap())
+    }
+}
+
+impl StateLock for RwLock<GameState> {
+    fn read_with<R>(&self, f: impl FnOnce(&GameState) -> R) -> R {
+        f(&self.read().unwrap())
+    }
+
+    fn write_with(&self, f: impl FnOnce(&mut GameState)) {
+        f(&mut self.write().unwrap())
+    }
+}
+
+/// The ticker moving every entity for a few frames while each session serializes the state for its client.
+fn tick_with_sessions(state: &impl StateLock, config: &GameConfig) {
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            for _ in 0..TICKS {
+                state.write_with(|state| {
+                    for entity in state.entities.values_mut() {
+                        entity.update(1.0 / 60.0, config);
+                    }
+                });
+            }
+        });
+
+        for _ in 0..SESSIONS {
+            scope.spawn(|| state.read_with(serde_json::to_string));
+        }
+    });
+}
+
+fn contention(c: &mut Criterion) {
+    let config = GameConfig::default();
+    let mutex = Mutex::new(crowded_state());
+    let rwlock = RwLock::new(crowded_state());
+
+    let mut group = c.benchmark_group("state_lock");
+    group.bench_function("mutex_50_sessions", |b| b.iter(|| tick_with_sessions(&mutex, &config)));
+    group.bench_function("rwlock_50_sessions", |b| b.iter(|| tick_with_sessions(&rwlock, &config)));
+    group.finish();
+}
+
+criterion_group!(benches, contention);
+criterion_main!(benches);
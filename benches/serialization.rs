@@ -0,0 +1,79 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use uuid::Uuid;
+use rust_game_server_practice::config::GameConfig;
+use rust_game_server_practice::game::{Bullet, GameState, Player, Team};
+use rust_game_server_practice::game::entity::EntityKind;
+use rust_game_server_practice::game::snapshot::{self, View};
+use rust_game_server_practice::geometry::vector::Vector2f;
+use rust_game_server_practice::message::{Encoding, MyMessage};
+
+fn config() -> GameConfig {
+    GameConfig { health_packs: 0, speed_boosts: 0, ..GameConfig::default() }
+}
+
+/// A state holding 200 entities, half players and half bullets.
+fn crowded_state() -> GameState {
+    let mut state = GameState::new(&config());
+
+    for i in 0..100 {
+        let position = Vector2f::new((i * 8) as f32, (i * 6) as f32);
+        let id = Uuid::new_v4();
+        state.entities.insert(id, EntityKind::Player(Player::new(id, Team::Red, position)));
+
+        let bullet = Bullet::new(Some(id), position, Vector2f::new(300.0, 0.0));
+        state.entities.insert(bullet.id, EntityKind::Bullet(bullet));
+    }
+
+    state
+}
+
+/// 100 players standing still on a grid, the case delta broadcasting is meant for.
+fn stationary_state() -> GameState {
+    let mut state = GameState::new(&config());
+
+    for i in 0..100 {
+        let position = Vector2f::new(40.0 + (i % 10) as f32 * 70.0, 40.0 + (i / 10) as f32 * 50.0);
+        let id = Uuid::new_v4();
+        state.entities.insert(id, EntityKind::Player(Player::new(id, Team::Red, position)));
+    }
+
+    state
+}
+
+fn serialization(c: &mut Criterion) {
+    let state = crowded_state();
+
+    c.bench_function("game_state_json", |b| {
+        b.iter(|| MyMessage::encode("game_state", black_box(&state), Encoding::Json))
+    });
+    c.bench_function("game_state_msgpack", |b| {
+        b.iter(|| MyMessage::encode("game_state", black_box(&state), Encoding::Binary))
+    });
+}
+
+/// Sizes up one tick's broadcast both ways: the full state a keyframe sends, and the delta sent otherwise.
+fn bandwidth(c: &mut Criterion) {
+    let config = config();
+    let mut state = stationary_state();
+    let previous = snapshot::capture(&state.entities);
+    state.step(1.0 / 60.0, &config);
+    let current = snapshot::capture(&state.entities);
+
+    let full = MyMessage::encode("game_state", &View::new(&state, &current), Encoding::Json).unwrap();
+    let delta = MyMessage::encode("game_delta", &snapshot::diff(state.ts, &previous, &current), Encoding::Json).unwrap();
+    println!("bytes per tick with 100 stationary players: full {}, delta {}", full.payload.len(), delta.payload.len());
+
+    let mut group = c.benchmark_group("stationary_100");
+    group.throughput(Throughput::Bytes(full.payload.len() as u64));
+    group.bench_function("full", |b| {
+        b.iter(|| MyMessage::encode("game_state", &View::new(&state, black_box(&current)), Encoding::Json))
+    });
+    group.throughput(Throughput::Bytes(delta.payload.len() as u64));
+    group.bench_function("delta", |b| {
+        b.iter(|| MyMessage::encode("game_delta", &snapshot::diff(state.ts, black_box(&previous), &current), Encoding::Json))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, serialization, bandwidth);
+criterion_main!(benches);
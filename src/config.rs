@@ -0,0 +1,221 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::game::{Wall, WorldBounds};
+use crate::geometry::quadtree::{DEFAULT_CAPACITY, DEFAULT_MIN_SIZE};
+use crate::geometry::spatial::DEFAULT_CELL_SIZE;
+use crate::geometry::vector::Vector2f;
+
+/// Structure used to narrow down which players a bullet might hit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadPhase {
+    #[default]
+    SpatialHash,
+    /// Copes better with players bunched into a few spots.
+    QuadTree,
+}
+
+/// Battle-royale mode: a safe zone centred on the world that shrinks while a round is active.
+/// Players outside it lose health, and the dead stay out until the round ends.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ShrinkingZone {
+    /// Pixels per second the radius shrinks by.
+    pub rate: f32,
+    pub min_radius: f32,
+    pub damage_per_second: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub world: WorldBounds,
+    /// How often state is broadcast. The simulation itself always advances in steps of `game::FIXED_DT`.
+    pub tick_ms: u64,
+    pub bullet_speed: f32,
+    /// Fire requests are ignored while this many bullets are alive.
+    pub max_bullets: usize,
+    pub max_player_speed: f32,
+    /// Connections beyond this many players are turned away with `server_full`.
+    pub max_players: usize,
+    /// Sessions silent for longer than this are dropped. Must be greater than `heartbeat_interval_secs`.
+    pub heartbeat_timeout_secs: u64,
+    /// How often sessions ping their client.
+    pub heartbeat_interval_secs: u64,
+    /// How long a dropped player is kept for its client to reconnect with its token.
+    pub reconnect_grace_secs: u64,
+    /// Messages per second each session regains. The web client sends a move every frame while a key is held.
+    pub message_rate: f32,
+    /// Most messages a session may send in one burst before being rate limited.
+    pub message_burst: f32,
+    /// How far from their player a session is sent entities. Unlimited by default.
+    pub view_radius: f32,
+    pub broad_phase: BroadPhase,
+    /// Cell size of the spatial hash that finds bullet hits.
+    pub collision_cell_size: f32,
+    /// Players a quadtree node holds before it splits.
+    pub quadtree_capacity: usize,
+    /// Smallest quadtree node that is still split.
+    pub quadtree_min_size: f32,
+    pub walls: Vec<Wall>,
+    /// Seeds spawn positions so they repeat between runs. Random when unset.
+    pub seed: Option<u64>,
+    /// Where players may spawn. Random positions are used when empty.
+    pub spawn_points: Vec<Vector2f>,
+    /// Number of health packs kept on the map.
+    pub health_packs: usize,
+    pub health_pack_heal: f32,
+    pub health_pack_respawn_secs: u64,
+    /// Number of speed boosts kept on the map.
+    pub speed_boosts: usize,
+    /// Multiplier applied to `max_player_speed` while a boost is active.
+    pub speed_boost_factor: f32,
+    pub speed_boost_secs: u64,
+    pub speed_boost_respawn_secs: u64,
+    /// Players needed in the lobby before a round counts down.
+    pub min_players: usize,
+    pub countdown_secs: u64,
+    /// Longest a round may last before it ends on points. 0 lets rounds run until a team wins.
+    pub match_duration_secs: u64,
+    pub shrinking_zone: Option<ShrinkingZone>,
+    /// Whether bullets hurt the shooter's teammates.
+    pub friendly_fire: bool,
+    /// Kills a team needs to win, after which scores start over. 0 disables the limit.
+    pub team_score_limit: u32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            world: WorldBounds::default(),
+            tick_ms: 16,
+            bullet_speed: 300.0,
+            max_bullets: 256,
+            max_player_speed: 250.0,
+            max_players: 16,
+            heartbeat_timeout_secs: 10,
+            heartbeat_interval_secs: 5,
+            reconnect_grace_secs: 30,
+            message_rate: 60.0,
+            message_burst: 120.0,
+            view_radius: f32::INFINITY,
+            broad_phase: BroadPhase::default(),
+            collision_cell_size: DEFAULT_CELL_SIZE,
+            quadtree_capacity: DEFAULT_CAPACITY,
+            quadtree_min_size: DEFAULT_MIN_SIZE,
+            walls: Vec::new(),
+            seed: None,
+            spawn_points: Vec::new(),
+            health_packs: 2,
+            health_pack_heal: 25.0,
+            health_pack_respawn_secs: 10,
+            speed_boosts: 1,
+            speed_boost_factor: 1.5,
+            speed_boost_secs: 5,
+            speed_boost_respawn_secs: 15,
+            min_players: 1,
+            countdown_secs: 3,
+            match_duration_secs: 300,
+            shrinking_zone: None,
+            friendly_fire: false,
+            team_score_limit: 50,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Reads a TOML config, falling back to the defaults when the file is missing or invalid.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let s = match std::fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(_) => return Self::default(),
+        };
+
+        let config = toml::from_str::<Self>(&s)
+            .map_err(|e| e.to_string())
+            .and_then(|config| config.validate().map(|_| config));
+
+        match config {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::warn!(error = %e, "invalid config, using defaults");
+                Self::default()
+            }
+        }
+    }
+
+    /// Rejects values that parse fine but would stall or spin the server, such as zero-length intervals.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.world.width > 0.0 && self.world.width.is_finite() && self.world.height > 0.0 && self.world.height.is_finite()) {
+            return Err("world width and height must be positive".to_string());
+        }
+
+        if self.tick_ms == 0 {
+            return Err("tick_ms must be at least 1".to_string());
+        }
+
+        if self.heartbeat_interval_secs == 0 {
+            return Err("heartbeat_interval_secs must be at least 1".to_string());
+        }
+
+        if self.heartbeat_timeout_secs <= self.heartbeat_interval_secs {
+            return Err("heartbeat_timeout_secs must be greater than heartbeat_interval_secs".to_string());
+        }
+
+        // Smaller cells, or quadtree nodes, multiply the work of every query without bound.
+        if !(self.collision_cell_size >= 1.0 && self.collision_cell_size.is_finite()) {
+            return Err("collision_cell_size must be at least 1".to_string());
+        }
+
+        if !(self.quadtree_min_size >= 1.0 && self.quadtree_min_size.is_finite()) {
+            return Err("quadtree_min_size must be at least 1".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(GameConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_zero_length_intervals() {
+        assert!(GameConfig { tick_ms: 0, ..GameConfig::default() }.validate().is_err());
+        assert!(GameConfig { heartbeat_interval_secs: 0, ..GameConfig::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_heartbeat_timeout_not_above_interval() {
+        let config = GameConfig {
+            heartbeat_timeout_secs: 5,
+            heartbeat_interval_secs: 5,
+            ..GameConfig::default()
+        };
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_degenerate_collision_sizes() {
+        assert!(GameConfig { collision_cell_size: 0.0, ..GameConfig::default() }.validate().is_err());
+        assert!(GameConfig { quadtree_min_size: 0.0, ..GameConfig::default() }.validate().is_err());
+        assert!(GameConfig { collision_cell_size: f32::NAN, ..GameConfig::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn load_falls_back_to_defaults_on_invalid_values() {
+        let path = std::env::temp_dir().join(format!("config-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "tick_ms = 0\nmax_players = 4\n").unwrap();
+
+        let config = GameConfig::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.tick_ms, GameConfig::default().tick_ms);
+        assert_eq!(config.max_players, GameConfig::default().max_players);
+    }
+}
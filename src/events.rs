@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use actix::{Actor, Context, Handler, Message};
+use actix_web::web::Bytes;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::error::TrySendError;
+
+/// Events buffered per subscriber before new ones are dropped for it.
+pub const SUBSCRIBER_BUFFER: usize = 64;
+
+/// Subscribers per room, shared with the games so they can skip encoding events nobody will read.
+/// Rooms without subscribers have no entry.
+pub type SubscriberCounts = Arc<RwLock<HashMap<String, usize>>>;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Subscribe {
+    pub room: String,
+    pub sender: Sender<Bytes>,
+}
+
+/// A conversation, already encoded as JSON, that happened in `room`.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Publish {
+    pub room: String,
+    pub event: String,
+}
+
+struct Subscriber {
+    room: String,
+    sender: Sender<Bytes>,
+}
+
+/// Fans game events out to read-only subscribers such as the `/events` SSE stream.
+pub struct EventBus {
+    subscribers: Vec<Subscriber>,
+    counts: SubscriberCounts,
+}
+
+impl EventBus {
+    pub fn new(counts: SubscriberCounts) -> Self {
+        Self {
+            subscribers: Vec::new(),
+            counts,
+        }
+    }
+}
+
+impl Actor for EventBus {
+    type Context = Context<Self>;
+}
+
+impl Handler<Subscribe> for EventBus {
+    type Result = ();
+
+    fn handle(&mut self, msg: Subscribe, _ctx: &mut Self::Context) -> Self::Result {
+        let mut counts = match self.counts.write() {
+            Ok(counts) => counts,
+            Err(e) => e.into_inner(),
+        };
+
+        *counts.entry(msg.room.clone()).or_default() += 1;
+
+        self.subscribers.push(Subscriber {
+            room: msg.room,
+            sender: msg.sender,
+        });
+    }
+}
+
+impl Handler<Publish> for EventBus {
+    type Result = ();
+
+    fn handle(&mut self, msg: Publish, _ctx: &mut Self::Context) -> Self::Result {
+        let frame = Bytes::from(format!("data: {}\n\n", msg.event));
+
+        let mut counts = match self.counts.write() {
+            Ok(counts) => counts,
+            Err(e) => e.into_inner(),
+        };
+
+        // A subscriber that can't keep up misses events; one that went away is dropped.
+        self.subscribers.retain(|subscriber| {
+            let open = if subscriber.room == msg.room {
+                !matches!(subscriber.sender.try_send(frame.clone()), Err(TrySendError::Closed(_)))
+            } else {
+                !subscriber.sender.is_closed()
+            };
+
+            if !open {
+                if let Some(count) = counts.get_mut(&subscriber.room) {
+                    *count -= 1;
+
+                    if *count == 0 {
+                        counts.remove(&subscriber.room);
+                    }
+                }
+            }
+
+            open
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use actix::Addr;
+    use crate::config::GameConfig;
+    use crate::game::Game;
+
+    /// A bus with one subscriber to `room`, and a game publishing to it.
+    async fn subscribed_game(room: &str) -> (SubscriberCounts, tokio::sync::mpsc::Receiver<Bytes>, Addr<Game>) {
+        let counts = SubscriberCounts::default();
+        let bus = EventBus::new(counts.clone()).start();
+        let (sender, receiver) = tokio::sync::mpsc::channel(SUBSCRIBER_BUFFER);
+        bus.send(Subscribe { room: room.to_string(), sender }).await.unwrap();
+
+        let game = Game::new(GameConfig::default()).with_events(bus, counts.clone(), room.to_string()).start();
+
+        (counts, receiver, game)
+    }
+
+    #[actix::test]
+    async fn subscriber_receives_game_state_events() {
+        let (_counts, mut receiver, _game) = subscribed_game("lobby").await;
+
+        let frame = actix::clock::timeout(Duration::from_secs(1), receiver.recv()).await.unwrap().unwrap();
+
+        let frame = String::from_utf8(frame.to_vec()).unwrap();
+        assert!(frame.starts_with("data: "));
+        assert!(frame.contains("\"kind\":\"game_state\""));
+    }
+
+    #[actix::test]
+    async fn nothing_is_published_for_rooms_without_subscribers() {
+        // Games only go by the shared count, so without it they skip the room even though the bus has a subscriber.
+        let (counts, mut receiver, _game) = subscribed_game("lobby").await;
+        counts.write().unwrap().remove("lobby");
+
+        actix::clock::sleep(Duration::from_millis(200)).await;
+
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use crate::config::GameConfig;
+use crate::game::{Bullet, EntityCommand, Explosion, HealthPack, Player, SpeedBoost, Wall};
+use crate::geometry::aabb::Aabb;
+
+/// Everything that can live in `GameState::entities`. Serializes with a `kind` tag naming the variant.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum EntityKind {
+    Player(Player),
+    Bullet(Bullet),
+    Wall(Wall),
+    HealthPack(HealthPack),
+    SpeedBoost(SpeedBoost),
+    Explosion(Explosion),
+}
+
+impl EntityKind {
+    pub fn update(&mut self, delta: f32, config: &GameConfig) -> EntityCommand {
+        match self {
+            EntityKind::Player(player) => player.update(delta, config),
+            EntityKind::Bullet(bullet) => bullet.update(delta, config),
+            EntityKind::Explosion(explosion) => explosion.update(delta),
+            EntityKind::Wall(_) | EntityKind::HealthPack(_) | EntityKind::SpeedBoost(_) => EntityCommand::Keep,
+        }
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        match self {
+            EntityKind::Player(player) => player.bounds(),
+            EntityKind::Bullet(bullet) => bullet.bounds(),
+            EntityKind::Wall(wall) => wall.bounds(),
+            EntityKind::HealthPack(pack) => Aabb::new(pack.position, pack.position),
+            EntityKind::SpeedBoost(boost) => Aabb::new(boost.position, boost.position),
+            EntityKind::Explosion(explosion) => explosion.bounds(),
+        }
+    }
+
+    pub fn as_player(&self) -> Option<&Player> {
+        match self {
+            EntityKind::Player(player) => Some(player),
+            _ => None,
+        }
+    }
+
+    pub fn as_player_mut(&mut self) -> Option<&mut Player> {
+        match self {
+            EntityKind::Player(player) => Some(player),
+            _ => None,
+        }
+    }
+
+    pub fn as_bullet(&self) -> Option<&Bullet> {
+        match self {
+            EntityKind::Bullet(bullet) => Some(bullet),
+            _ => None,
+        }
+    }
+
+    pub fn as_wall(&self) -> Option<&Wall> {
+        match self {
+            EntityKind::Wall(wall) => Some(wall),
+            _ => None,
+        }
+    }
+
+    pub fn as_explosion(&self) -> Option<&Explosion> {
+        match self {
+            EntityKind::Explosion(explosion) => Some(explosion),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+    use crate::game::Team;
+    use crate::geometry::vector::Vector2f;
+
+    #[test]
+    fn player_variant_moves_with_its_velocity() {
+        let mut player = Player::new(Uuid::new_v4(), Team::Red, Vector2f::new(100.0, 100.0));
+        player.velocity = Vector2f::new(60.0, 0.0);
+        let mut entity = EntityKind::Player(player);
+
+        entity.update(0.1, &GameConfig::default());
+
+        let position = entity.as_player().unwrap().position;
+        assert!(position.x > 100.0);
+        assert_eq!(position.y, 100.0);
+    }
+
+    #[test]
+    fn bullet_variant_moves_and_ages() {
+        let bullet = Bullet::new(None, Vector2f::new(100.0, 100.0), Vector2f::new(100.0, 0.0));
+        let lifetime = bullet.lifetime;
+        let mut entity = EntityKind::Bullet(bullet);
+
+        entity.update(0.1, &GameConfig::default());
+
+        let bullet = entity.as_bullet().unwrap();
+        assert!((bullet.position.x - 110.0).abs() < 1e-3);
+        assert!(bullet.lifetime < lifetime);
+    }
+
+    #[test]
+    fn entities_round_trip_with_their_kind_tag() {
+        let entity = EntityKind::Bullet(Bullet::new(None, Vector2f::new(1.0, 2.0), Vector2f::new(3.0, 4.0)));
+
+        let value = serde_json::to_value(&entity).unwrap();
+        assert_eq!(value["kind"], "Bullet");
+
+        let entity: EntityKind = serde_json::from_value(value).unwrap();
+        assert!(entity.as_bullet().is_some());
+    }
+}
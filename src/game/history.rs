@@ -0,0 +1,141 @@
+use std::collections::{HashMap, VecDeque};
+use uuid::Uuid;
+use crate::game::Bullet;
+use crate::game::entity::EntityKind;
+use crate::geometry::aabb::Aabb;
+use crate::geometry::vector::Vector2f;
+
+/// Player positions and radii as of one broadcast `ts`.
+struct Frame {
+    ts: i64,
+    players: Vec<(Uuid, Vector2f, f32)>,
+}
+
+/// Ring buffer of recent player positions, so shots can be tested against what a lagging client saw.
+pub struct History {
+    frames: VecDeque<Frame>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn record(&mut self, ts: i64, entities: &HashMap<Uuid, EntityKind>) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+
+        let players = entities
+            .iter()
+            .filter_map(|(id, entity)| entity.as_player().map(|player| (*id, player.position, player.radius)))
+            .collect();
+
+        self.frames.push_back(Frame { ts, players });
+    }
+
+    /// Flies `bullet` from the frame closest before `ts` up to the newest one, testing it against each frame's players.
+    /// Returns the first player hit that `ignore` lets through; otherwise the bullet is left where it would be now.
+    /// A bullet reaching one of `walls` stops there, for the next step to destroy or bounce it.
+    pub fn rewind(&self, bullet: &mut Bullet, ts: i64, walls: &[Aabb], ignore: impl Fn(&Uuid) -> bool) -> Option<Uuid> {
+        let start = self.frames
+            .iter()
+            .rposition(|frame| frame.ts <= ts)
+            .unwrap_or(0);
+
+        for (index, frame) in self.frames.iter().enumerate().skip(start) {
+            if walls.iter().any(|wall| wall.intersects(&bullet.bounds())) {
+                return None;
+            }
+
+            let hit = frame.players.iter().find(|(id, position, radius)| {
+                bullet.owner != Some(*id)
+                    && !ignore(id)
+                    && bullet.hits(position, *radius)
+            });
+
+            if let Some((id, _, _)) = hit {
+                return Some(*id);
+            }
+
+            if let Some(next) = self.frames.get(index + 1) {
+                let delta = (next.ts - frame.ts) as f32 / 1000.0;
+                bullet.advance(delta);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Player, Team};
+
+    /// Records ten frames, 16ms apart, of one player standing still at `position`.
+    fn history_with_player(position: Vector2f) -> (History, Uuid) {
+        let id = Uuid::new_v4();
+        let entities = HashMap::from([(id, EntityKind::Player(Player::new(id, Team::Red, position)))]);
+        let mut history = History::new(10);
+
+        for frame in 0..10 {
+            history.record(frame * 16, &entities);
+        }
+
+        (history, id)
+    }
+
+    fn bullet_heading_right() -> Bullet {
+        Bullet::new(None, Vector2f::new(0.0, 100.0), Vector2f::new(600.0, 0.0))
+    }
+
+    #[test]
+    fn rewound_bullet_hits_player_in_its_path() {
+        let (history, id) = history_with_player(Vector2f::new(60.0, 100.0));
+
+        assert_eq!(history.rewind(&mut bullet_heading_right(), 0, &[], |_| false), Some(id));
+    }
+
+    #[test]
+    fn rewind_skips_ignored_players() {
+        let (history, id) = history_with_player(Vector2f::new(60.0, 100.0));
+
+        assert_eq!(history.rewind(&mut bullet_heading_right(), 0, &[], |other| *other == id), None);
+    }
+
+    #[test]
+    fn rewound_bullet_hits_where_a_moving_player_was() {
+        let id = Uuid::new_v4();
+        let mut player = Player::new(id, Team::Red, Vector2f::new(60.0, 100.0));
+        let mut history = History::new(10);
+
+        for frame in 0..10 {
+            // Late in the window, the player steps out of the bullet's path.
+            if frame == 8 {
+                player.position = Vector2f::new(60.0, 300.0);
+            }
+
+            let entities = HashMap::from([(id, EntityKind::Player(player.clone()))]);
+            history.record(frame * 16, &entities);
+        }
+
+        assert_eq!(history.rewind(&mut bullet_heading_right(), 0, &[], |_| false), Some(id));
+        // Without a ts the shot is only tested against where the player is now.
+        assert_eq!(history.rewind(&mut bullet_heading_right(), 9 * 16, &[], |_| false), None);
+    }
+
+    #[test]
+    fn rewound_bullet_stops_at_walls() {
+        let (history, _) = history_with_player(Vector2f::new(60.0, 100.0));
+        let wall = Aabb::new(Vector2f::new(20.0, 0.0), Vector2f::new(40.0, 200.0));
+        let mut bullet = bullet_heading_right();
+
+        assert_eq!(history.rewind(&mut bullet, 0, &[wall], |_| false), None);
+        assert!(wall.intersects(&bullet.bounds()));
+    }
+}
@@ -1,29 +1,181 @@
-use std::any::Any;
+pub mod entity;
+pub mod history;
+pub mod snapshot;
+pub mod weapon;
+
 use std::collections::HashMap;
-use std::ops::{Deref, DerefMut};
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant, SystemTime};
-use actix::{Actor, ActorContext, AsyncContext, Context, Handler, Recipient};
-use serde::{Serialize};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use actix::{Actor, ActorContext, Addr, AsyncContext, Context, Handler, Recipient};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::config::{BroadPhase, GameConfig};
+use crate::events::{EventBus, Publish, SubscriberCounts};
+use crate::game::entity::EntityKind;
+use crate::game::history::History;
+use crate::game::snapshot::Snapshot;
+use crate::game::weapon::Weapon;
+use crate::geometry::aabb::Aabb;
+use crate::geometry::quadtree::QuadTree;
+use crate::geometry::spatial::SpatialHash;
 use crate::geometry::vector::Vector2f;
-use crate::message::{Connect, Conversation, Disconnect, MyMessage, WrappedConversation};
+use crate::metrics;
+use crate::message::{ChatEvent, ClientInput, Connect, ConnectError, Disconnect, Encoding, GameOver, InputFrame, KillEvent, KillFeed, MyMessage, PickupEvent, Ping, PingResponse, PlayerDied, PlayerPresence, PlayerScore, RoundOver, SetEncoding, SpeedBoostEvent, TeamAssigned, WrappedInput};
+
+/// Fraction of a player's velocity kept per 60Hz frame; scaled by `delta` so it is framerate independent.
+pub const PLAYER_DAMPING: f32 = 0.92;
+/// Speed, in pixels per second, a player gains each second while thrusting at full strength.
+pub const PLAYER_ACCELERATION: f32 = 1200.0;
+pub const PLAYER_RADIUS: f32 = 10.0;
+pub const MAX_PLAYER_HEALTH: f32 = 100.0;
+/// Longest name, in characters, a player may pick.
+pub const MAX_NAME_LEN: usize = 16;
+/// Chat messages longer than this many characters, after trimming, are dropped.
+pub const MAX_CHAT_LEN: usize = 256;
+/// Distance from a player's centre within which pickups are collected.
+pub const PICKUP_RADIUS: f32 = 16.0;
+/// A bullet hits a player once their centres are closer than the sum of their radii.
+pub const BULLET_RADIUS: f32 = 4.0;
+pub const BULLET_DAMAGE: f32 = 10.0;
+pub const BULLET_LIFETIME_SECS: f32 = 3.0;
+/// Distance a bullet travels at full damage.
+pub const FALLOFF_START: f32 = 150.0;
+/// Distance past which a bullet only deals `MIN_DAMAGE_FRACTION` of its damage.
+pub const FALLOFF_END: f32 = 600.0;
+pub const MIN_DAMAGE_FRACTION: f32 = 0.4;
+/// Fraction of a bullet's velocity passed on to the player it hits.
+pub const KNOCKBACK_FACTOR: f32 = 0.5;
+/// Most speed a single hit can add, so fast bullets don't fling players across the map.
+pub const MAX_KNOCKBACK: f32 = 200.0;
+pub const EXPLOSION_RADIUS: f32 = 60.0;
+/// Damage an explosion deals in total to a player who stays in it for its whole duration.
+pub const EXPLOSION_DAMAGE: f32 = 50.0;
+pub const EXPLOSION_SECS: f32 = 0.5;
+/// How far past the world edge a bullet may travel before it is dropped.
+pub const BULLET_BOUNDS_MARGIN: f32 = 16.0;
+pub const RESPAWN_DELAY: Duration = Duration::from_secs(3);
+pub const FIRE_COOLDOWN: Duration = Duration::from_millis(250);
+/// Shots in a full magazine; a shotgun blast counts as one.
+pub const MAX_AMMO: u8 = 12;
+pub const RELOAD_DURATION: Duration = Duration::from_millis(1500);
+/// Ticks of player positions kept for rewinding shots; about half a second at the default tick rate.
+pub const HISTORY_LEN: usize = 32;
+/// Seconds simulated by each `GameState::step`, however long the ticks in between actually take.
+pub const FIXED_DT: f32 = 1.0 / 60.0;
+/// Most steps one tick may run to catch up; time beyond that is dropped so a slow tick can't snowball.
+pub const MAX_STEPS_PER_TICK: usize = 5;
+/// Every this many ticks a full game_state is sent instead of a game_delta.
+pub const KEYFRAME_INTERVAL: u64 = 60;
+/// How often a countdown announces the time left.
+pub const COUNTDOWN_STEP: Duration = Duration::from_secs(1);
+/// How long the result of a round is shown before the game resets to the lobby.
+pub const GAME_OVER_DELAY: Duration = Duration::from_secs(5);
+
+/// What happens to a player that reaches the edge of the world.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundaryMode {
+    /// Pushed back in, keeping most of its speed. Bullets leaving the world are dropped.
+    #[default]
+    Bounce,
+    /// Comes out of the opposite edge, as do bullets.
+    Wrap,
+    /// Stops at the edge. Bullets leaving the world are dropped.
+    Clamp,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldBounds {
+    pub width: f32,
+    pub height: f32,
+    pub boundary: BoundaryMode,
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        Self {
+            width: 800.0,
+            height: 600.0,
+            boundary: BoundaryMode::default(),
+        }
+    }
+}
+
+impl WorldBounds {
+    pub fn center(&self) -> Vector2f {
+        Vector2f::new(self.width / 2.0, self.height / 2.0)
+    }
+
+    pub fn random_position(&self, rng: &fastrand::Rng) -> Vector2f {
+        Vector2f::new(rng.f32() * self.width, rng.f32() * self.height)
+    }
 
-#[typetag::serialize(tag = "kind")]
-pub trait Entity {
-    fn update(&mut self, delta: f32);
+    /// Whether `position` lies within one world's width or height of the world itself.
+    pub fn is_near(&self, position: Vector2f) -> bool {
+        position.x >= -self.width
+            && position.x <= self.width * 2.0
+            && position.y >= -self.height
+            && position.y <= self.height * 2.0
+    }
+
+    /// Brings a position that left the world back in through the opposite edge.
+    pub fn wrap(&self, position: Vector2f) -> Vector2f {
+        Vector2f::new(position.x.rem_euclid(self.width), position.y.rem_euclid(self.height))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Team {
+    Red,
+    Blue,
+}
+
+/// What last hurt a player, reported with their death.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DamageCause {
+    #[default]
+    Bullet,
+    Explosion,
+    Zone,
+}
 
-    fn as_any(&self) -> &dyn Any;
+/// Stage of a round. Players can only move and fire while it is `Active`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GamePhase {
+    /// Waiting for `min_players` to join.
+    #[default]
+    Lobby,
+    Countdown { remaining_ms: u64 },
+    Active,
+    GameOver { winner: Option<Team> },
+}
 
-    fn as_any_mut(&mut self) -> &mut dyn Any;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityCommand {
+    Keep,
+    Remove,
+    /// Remove the entity, leaving an `Explosion` in its place.
+    Explode,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bullet {
     pub id: Uuid,
     pub owner: Option<Uuid>,
     pub position: Vector2f,
     pub velocity: Vector2f,
+    pub radius: f32,
+    pub damage: f32,
+    /// Whether the bullet explodes, instead of just disappearing, when it hits something or runs out of time.
+    pub explosive: bool,
+    /// Seconds left before the bullet despawns.
+    pub lifetime: f32,
+    /// Distance flown so far, which weakens the hit.
+    pub traveled: f32,
 }
 
 impl Bullet {
@@ -33,246 +185,3212 @@ impl Bullet {
             owner,
             position,
             velocity,
+            radius: BULLET_RADIUS,
+            damage: BULLET_DAMAGE,
+            explosive: false,
+            lifetime: BULLET_LIFETIME_SECS,
+            traveled: 0.0,
         }
     }
-}
-
-#[typetag::serialize]
-impl Entity for Bullet {
-    fn update(&mut self, delta: f32) {
-        self.position.x += self.velocity.x * delta;
-        self.position.y += self.velocity.y * delta;
 
+    pub fn with_damage(mut self, damage: f32) -> Self {
+        self.damage = damage;
+        self
     }
 
-    fn as_any(&self) -> &dyn Any {
+    /// Makes the bullet explode after `fuse` seconds.
+    pub fn with_fuse(mut self, fuse: f32) -> Self {
+        self.lifetime = fuse;
+        self.explosive = true;
         self
     }
 
-    fn as_any_mut(&mut self) -> &mut dyn Any {
+    pub fn with_lifetime(mut self, lifetime: f32) -> Self {
+        self.lifetime = lifetime;
         self
     }
+
+    /// Damage the bullet deals if it hits now: full up to `FALLOFF_START`, then dropping linearly until `FALLOFF_END`.
+    pub fn effective_damage(&self) -> f32 {
+        let falloff = ((self.traveled - FALLOFF_START) / (FALLOFF_END - FALLOFF_START)).clamp(0.0, 1.0);
+        self.damage * (1.0 - falloff * (1.0 - MIN_DAMAGE_FRACTION))
+    }
+
+    /// Moves the bullet along its path for `delta` seconds.
+    pub fn advance(&mut self, delta: f32) {
+        self.position += self.velocity * delta;
+        self.traveled += self.velocity.magnitude() * delta;
+        self.lifetime -= delta;
+    }
+
+    pub fn update(&mut self, delta: f32, config: &GameConfig) -> EntityCommand {
+        self.advance(delta);
+
+        if config.world.boundary == BoundaryMode::Wrap {
+            self.position = config.world.wrap(self.position);
+        }
+
+        let in_bounds = self.position.x >= -BULLET_BOUNDS_MARGIN
+            && self.position.x <= config.world.width + BULLET_BOUNDS_MARGIN
+            && self.position.y >= -BULLET_BOUNDS_MARGIN
+            && self.position.y <= config.world.height + BULLET_BOUNDS_MARGIN;
+
+        if self.lifetime <= 0.0 && self.explosive {
+            return EntityCommand::Explode;
+        }
+
+        if self.lifetime <= 0.0 || !in_bounds {
+            return EntityCommand::Remove;
+        }
+
+        EntityCommand::Keep
+    }
+
+    /// Whether the bullet overlaps a circle of `radius` around `position`.
+    pub fn hits(&self, position: &Vector2f, radius: f32) -> bool {
+        let reach = self.radius + radius;
+        self.position.distance_squared(position) < reach * reach
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_center(self.position, Vector2f::new(self.radius, self.radius))
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
     pub id: Uuid,
+    pub name: String,
+    pub team: Team,
     pub health: f32,
     pub position: Vector2f,
+    pub radius: f32,
     pub velocity: Vector2f,
+    /// Direction the player last asked to move in, at most unit length. Applied on the next update, then cleared.
+    #[serde(skip)]
+    pub thrust: Vector2f,
+    pub kills: u32,
+    pub deaths: u32,
+    pub current_weapon: Weapon,
+    pub ammo: u8,
+    pub max_ammo: u8,
+    pub is_reloading: bool,
+    /// Owner of the last bullet or explosion that hit this player, cleared by anything nobody owns.
+    #[serde(skip)]
+    pub last_hit_by: Option<Uuid>,
+    #[serde(skip)]
+    pub last_damage_cause: DamageCause,
+    #[serde(skip)]
+    pub speed_boost_until: Option<Instant>,
 }
 
 impl Player {
-    pub fn new(id: Uuid) -> Self {
+    pub fn new(id: Uuid, team: Team, position: Vector2f) -> Self {
         Self {
             id,
-            health: 100.0,
-            position: Vector2f::new(fastrand::f32() * 800.0, fastrand::f32() * 600.0),
+            name: String::new(),
+            team,
+            health: MAX_PLAYER_HEALTH,
+            position,
+            radius: PLAYER_RADIUS,
             velocity: Vector2f::new(0.0, 0.0),
+            thrust: Vector2f::default(),
+            kills: 0,
+            deaths: 0,
+            current_weapon: Weapon::default(),
+            ammo: MAX_AMMO,
+            max_ammo: MAX_AMMO,
+            is_reloading: false,
+            last_hit_by: None,
+            last_damage_cause: DamageCause::default(),
+            speed_boost_until: None,
         }
     }
-}
 
-#[typetag::serialize]
-impl Entity for Player {
-    fn update(&mut self, delta: f32) {
-        self.position.x += self.velocity.x * delta;
-        self.position.y += self.velocity.y * delta;
+    /// Strips control characters and surrounding whitespace, then truncates to `MAX_NAME_LEN` characters.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name
+            .trim()
+            .chars()
+            .filter(|c| !c.is_control())
+            .take(MAX_NAME_LEN)
+            .collect();
+    }
+
+    /// The chosen name, or the start of the id for players who never set one.
+    pub fn display_name(&self) -> String {
+        if self.name.is_empty() {
+            self.id.to_string()[..8].to_string()
+        } else {
+            self.name.clone()
+        }
+    }
+
+    pub fn max_speed(&self, config: &GameConfig) -> f32 {
+        match self.speed_boost_until {
+            Some(_) => config.max_player_speed * config.speed_boost_factor,
+            None => config.max_player_speed,
+        }
+    }
+
+    pub fn update(&mut self, delta: f32, config: &GameConfig) -> EntityCommand {
+        if self.speed_boost_until.is_some_and(|until| Instant::now() >= until) {
+            self.speed_boost_until = None;
+        }
+
+        self.velocity += self.thrust * PLAYER_ACCELERATION * delta;
+        self.thrust = Vector2f::default();
+
+        self.velocity = self.velocity.clamp_magnitude(self.max_speed(config));
+        self.position += self.velocity * delta;
+        self.velocity = self.velocity * PLAYER_DAMPING.powf(delta * 60.0);
+
+        match config.world.boundary {
+            BoundaryMode::Bounce => self.bounce(config.world),
+            BoundaryMode::Wrap => self.position = config.world.wrap(self.position),
+            BoundaryMode::Clamp => self.clamp(config.world),
+        }
+
+        EntityCommand::Keep
+    }
 
+    fn bounce(&mut self, world: WorldBounds) {
         if self.position.x < 0.0 {
             self.position.x = 0.0;
-            self.velocity.x *= -0.8;
-        } else if self.position.x > 800.0 {
-            self.position.x = 800.0;
-            self.velocity.x *= -0.8;
+            self.velocity = self.velocity.reflect(&Vector2f::new(1.0, 0.0)) * 0.8;
+        } else if self.position.x > world.width {
+            self.position.x = world.width;
+            self.velocity = self.velocity.reflect(&Vector2f::new(-1.0, 0.0)) * 0.8;
         }
 
         if self.position.y < 0.0 {
             self.position.y = 0.0;
-            self.velocity.y *= -0.8;
-        } else if self.position.y > 600.0 {
-            self.position.y = 600.0;
-            self.velocity.y *= -0.8;
+            self.velocity = self.velocity.reflect(&Vector2f::new(0.0, 1.0)) * 0.8;
+        } else if self.position.y > world.height {
+            self.position.y = world.height;
+            self.velocity = self.velocity.reflect(&Vector2f::new(0.0, -1.0)) * 0.8;
         }
     }
 
-    fn as_any(&self) -> &dyn Any {
-        self
+    /// Holds the player at the edge, dropping the speed that would carry it out.
+    fn clamp(&mut self, world: WorldBounds) {
+        if self.position.x < 0.0 || self.position.x > world.width {
+            self.position.x = self.position.x.clamp(0.0, world.width);
+            self.velocity.x = 0.0;
+        }
+
+        if self.position.y < 0.0 || self.position.y > world.height {
+            self.position.y = self.position.y.clamp(0.0, world.height);
+            self.velocity.y = 0.0;
+        }
     }
 
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_center(self.position, Vector2f::new(self.radius, self.radius))
     }
 }
 
-#[derive(Serialize)]
-pub struct GameState {
-    pub ts: i64,
-    pub entities: HashMap<Uuid, Box<dyn Entity>>,
+/// A static, axis-aligned obstacle. `position` is its top-left corner.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Wall {
+    pub position: Vector2f,
+    pub size: Vector2f,
+    /// Whether bullets ricochet off this wall instead of being destroyed.
+    #[serde(default)]
+    pub bounces_bullets: bool,
 }
 
-impl Default for GameState {
-    fn default() -> Self {
+impl Wall {
+    pub fn new(position: Vector2f, size: Vector2f, bounces_bullets: bool) -> Self {
         Self {
-            ts: chrono::Utc::now().timestamp_millis(),
-            entities: HashMap::new(),
+            position,
+            size,
+            bounces_bullets,
         }
     }
-}
 
-type Session = Recipient<MyMessage>;
+    pub fn bounds(&self) -> Aabb {
+        Aabb::new(self.position, self.position + self.size)
+    }
+}
 
-pub struct Game {
-    state: Arc<Mutex<GameState>>,
-    sessions: HashMap<Uuid, Session>,
-    start_time: Instant,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthPack {
+    pub position: Vector2f,
+    pub heal_amount: f32,
 }
 
-impl Default for Game {
-    fn default() -> Self {
+impl HealthPack {
+    pub fn new(position: Vector2f, heal_amount: f32) -> Self {
         Self {
-            state: Arc::new(Mutex::new(GameState::default())),
-            sessions: HashMap::new(),
-            start_time: Instant::now(),
+            position,
+            heal_amount,
         }
     }
 }
 
-impl Game {
-    fn notify<T: Serialize>(&self, conversation: Conversation<T>) {
-        let msg = match serde_json::to_string(&conversation) {
-            Ok(msg) => msg,
-            Err(_) => return,
-        };
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeedBoost {
+    pub position: Vector2f,
+}
+
+impl SpeedBoost {
+    pub fn new(position: Vector2f) -> Self {
+        Self { position }
+    }
+}
+
+/// Area damage left by an explosive bullet: `damage` spread over `duration` seconds to every player within `radius`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Explosion {
+    pub owner: Option<Uuid>,
+    pub position: Vector2f,
+    pub radius: f32,
+    pub damage: f32,
+    /// Seconds left before the explosion fades.
+    pub lifetime: f32,
+    /// Seconds the explosion lasts in total, over which `damage` is spread.
+    pub duration: f32,
+}
 
-        for (_, addr) in self.sessions.iter() {
-            let _ = addr.do_send(MyMessage(msg.clone()));
+impl Explosion {
+    pub fn new(owner: Option<Uuid>, position: Vector2f, damage: f32) -> Self {
+        Self {
+            owner,
+            position,
+            radius: EXPLOSION_RADIUS,
+            damage,
+            lifetime: EXPLOSION_SECS,
+            duration: EXPLOSION_SECS,
         }
     }
 
-    fn start_ticker(&mut self, ctx: &mut <Self as Actor>::Context) {
-        self.start_time = Instant::now();
+    pub fn update(&mut self, delta: f32) -> EntityCommand {
+        self.lifetime -= delta;
 
-        ctx.run_interval(Duration::from_millis(16), |act, _ctx| {
-            let current_time = Instant::now();
-            let delta = current_time.duration_since(act.start_time).as_secs_f32();
-            act.start_time = current_time;
+        if self.lifetime <= 0.0 {
+            return EntityCommand::Remove;
+        }
 
-            let mut state = match act.state.lock() {
-                Ok(state) => state,
-                Err(_) => return,
-            };
+        EntityCommand::Keep
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_center(self.position, Vector2f::new(self.radius, self.radius))
+    }
+}
+
+/// Player positions indexed by whichever `BroadPhase` is configured.
+enum CollisionIndex {
+    SpatialHash(SpatialHash),
+    QuadTree(QuadTree),
+}
+
+impl Default for CollisionIndex {
+    fn default() -> Self {
+        Self::SpatialHash(SpatialHash::default())
+    }
+}
 
-            for entity in state.entities.values_mut() {
-                entity.update(delta);
+impl CollisionIndex {
+    fn new(config: &GameConfig) -> Self {
+        match config.broad_phase {
+            BroadPhase::SpatialHash => Self::SpatialHash(SpatialHash::new(config.collision_cell_size)),
+            BroadPhase::QuadTree => {
+                let world = Aabb::new(Vector2f::default(), Vector2f::new(config.world.width, config.world.height));
+                Self::QuadTree(QuadTree::with_limits(world, config.quadtree_capacity, config.quadtree_min_size))
             }
+        }
+    }
 
-            state.ts = chrono::Utc::now().timestamp_millis();
+    fn clear(&mut self) {
+        match self {
+            Self::SpatialHash(hash) => hash.clear(),
+            Self::QuadTree(tree) => tree.clear(),
+        }
+    }
 
-            let data = match serde_json::to_string(&state.deref()) {
-                Ok(msg) => msg,
-                Err(_) => return,
-            };
+    fn insert(&mut self, id: Uuid, position: Vector2f) {
+        match self {
+            Self::SpatialHash(hash) => hash.insert(id, position),
+            Self::QuadTree(tree) => tree.insert(id, Aabb::new(position, position)),
+        }
+    }
 
-            let conversation = Conversation::new("game_state".to_string(), data);
-            act.notify(conversation);
-        });
+    /// Ids that may lie within `radius` of `position`; callers still need an exact distance check.
+    fn candidates(&self, position: Vector2f, radius: f32) -> Vec<Uuid> {
+        match self {
+            Self::SpatialHash(hash) => hash.query_radius(position, radius),
+            Self::QuadTree(tree) => tree.query(&Aabb::from_center(position, Vector2f::new(radius, radius))),
+        }
     }
 }
 
-impl Actor for Game {
-    type Context = Context<Self>;
+/// Things that happened during a `GameState::step` which sessions should hear about.
+#[derive(Debug, Default)]
+pub struct TickEvents {
+    pub deaths: Vec<PlayerDied>,
+    pub kills: Vec<KillEvent>,
+    pub kill_feed: Vec<KillFeed>,
+    pub pickups: Vec<PickupEvent>,
+    pub speed_boosts: Vec<SpeedBoostEvent>,
+    pub game_over: Option<GameOver>,
+}
 
-    fn started(&mut self, ctx: &mut Self::Context) {
-        self.start_ticker(ctx);
+#[derive(Serialize, Deserialize)]
+pub struct GameState {
+    pub ts: i64,
+    pub phase: GamePhase,
+    pub world: WorldBounds,
+    /// Radius of the safe zone around the world centre, when playing with a shrinking zone.
+    pub safe_zone_radius: Option<f32>,
+    pub entities: HashMap<Uuid, EntityKind>,
+    /// Kills scored by each team since the last game over.
+    pub scores: HashMap<Team, u32>,
+    /// Players waiting to respawn, kept so their stats carry over.
+    #[serde(skip)]
+    pub dead_players: HashMap<Uuid, Player>,
+    /// Player positions for the collision pass, rebuilt every step.
+    #[serde(skip)]
+    collision_index: CollisionIndex,
+    /// Seed for the next random draw. Kept as a plain number because `fastrand::Rng` is not `Sync`.
+    #[serde(skip)]
+    rng_seed: u64,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new(&GameConfig::default())
     }
 }
 
-impl Handler<Connect> for Game {
-    type Result = ();
+impl GameState {
+    pub fn new(config: &GameConfig) -> Self {
+        Self::with_rng_seed(config, config.seed.unwrap_or_else(|| fastrand::u64(..)))
+    }
 
-    fn handle(&mut self, msg: Connect, ctx: &mut Self::Context) -> Self::Result {
-        let mut state = match self.state.lock() {
-            Ok(state) => state,
-            Err(_) => {
-                ctx.stop();
-                return;
-            },
+    fn with_rng_seed(config: &GameConfig, rng_seed: u64) -> Self {
+        let mut entities: HashMap<Uuid, EntityKind> = HashMap::new();
+
+        for wall in config.walls.iter() {
+            entities.insert(Uuid::new_v4(), EntityKind::Wall(wall.clone()));
+        }
+
+        let mut state = Self {
+            ts: chrono::Utc::now().timestamp_millis(),
+            phase: GamePhase::Lobby,
+            world: config.world,
+            safe_zone_radius: config.shrinking_zone.map(|_| config.world.center().magnitude()),
+            entities,
+            scores: HashMap::from([(Team::Red, 0), (Team::Blue, 0)]),
+            dead_players: HashMap::new(),
+            collision_index: CollisionIndex::new(config),
+            rng_seed,
         };
 
-        self.sessions.insert(msg.id, msg.addr);
-        state.entities.insert(msg.id, Box::new(Player::new(msg.id)));
+        for _ in 0..config.health_packs {
+            state.spawn_health_pack(config);
+        }
+
+        for _ in 0..config.speed_boosts {
+            state.spawn_speed_boost(config);
+        }
+
+        state
     }
-}
 
-impl Handler<Disconnect> for Game {
-    type Result = ();
+    /// Starts over with fresh pickups and scores, respawning everyone with their name and team but no kills or deaths.
+    pub fn reset(&mut self, config: &GameConfig) {
+        let players: Vec<Player> = self.entities
+            .values()
+            .filter_map(|entity| entity.as_player())
+            .chain(self.dead_players.values())
+            .cloned()
+            .collect();
 
-    fn handle(&mut self, msg: Disconnect, ctx: &mut Self::Context) -> Self::Result {
-        let mut state = match self.state.lock() {
-            Ok(state) => state,
-            Err(_) => {
-                ctx.stop();
-                return;
+        // Carrying the generator over keeps a seeded game's rounds from all replaying the first one.
+        *self = Self::with_rng_seed(config, self.rng_seed);
+
+        for player in players {
+            let id = player.id;
+            self.dead_players.insert(id, Player { kills: 0, deaths: 0, ..player });
+            self.spawn_player(id, config);
+        }
+    }
+
+    /// The generator for one random draw, advancing the stored seed so the next draw differs.
+    fn rng(&mut self) -> fastrand::Rng {
+        let rng = fastrand::Rng::with_seed(self.rng_seed);
+        self.rng_seed = rng.u64(..);
+        rng
+    }
+
+    fn spawn_health_pack(&mut self, config: &GameConfig) {
+        let rng = self.rng();
+        let pack = HealthPack::new(self.world.random_position(&rng), config.health_pack_heal);
+        self.entities.insert(Uuid::new_v4(), EntityKind::HealthPack(pack));
+    }
+
+    fn spawn_speed_boost(&mut self, _config: &GameConfig) {
+        let rng = self.rng();
+        let boost = SpeedBoost::new(self.world.random_position(&rng));
+        self.entities.insert(Uuid::new_v4(), EntityKind::SpeedBoost(boost));
+    }
+
+    /// Advances the simulation by `delta` seconds.
+    pub fn step(&mut self, delta: f32, config: &GameConfig) -> TickEvents {
+        let mut detonated = Vec::new();
+
+        self.entities.retain(|_, entity| match entity.update(delta, config) {
+            EntityCommand::Keep => true,
+            EntityCommand::Remove => false,
+            EntityCommand::Explode => {
+                detonated.extend(entity.as_bullet().cloned());
+                false
             }
+        });
+
+        for bullet in detonated {
+            self.detonate(&bullet);
+        }
+
+        self.resolve_walls();
+        self.resolve_collisions(config);
+        self.apply_explosions(delta, config);
+        self.shrink_zone(delta, config);
+        self.ts = chrono::Utc::now().timestamp_millis();
+
+        let deaths = self.remove_dead_players();
+        let kills = deaths.iter().filter_map(|death| self.credit_kill(death)).collect();
+        let kill_feed = deaths.iter().map(|death| self.kill_feed(death)).collect();
+        let pickups = self.collect_health_packs();
+        let speed_boosts = self.collect_speed_boosts(config);
+        let game_over = self.check_score_limit(config).or_else(|| self.check_last_team_standing(config));
+
+        TickEvents { deaths, kills, kill_feed, pickups, speed_boosts, game_over }
+    }
+
+    /// Ends the game once a team reaches `team_score_limit`, clearing the tally for the next one.
+    /// The highest score wins; teams passing the limit level with each other draw, with no winner.
+    fn check_score_limit(&mut self, config: &GameConfig) -> Option<GameOver> {
+        if config.team_score_limit == 0 || self.phase != GamePhase::Active {
+            return None;
+        }
+
+        let top = self.scores.values().copied().max()?;
+
+        if top < config.team_score_limit {
+            return None;
+        }
+
+        let mut leaders = self.scores.iter().filter(|(_, score)| **score == top).map(|(team, _)| *team);
+        let winner = match (leaders.next(), leaders.next()) {
+            (Some(team), None) => Some(team),
+            _ => None,
         };
 
-        self.sessions.remove(&msg.id);
-        state.entities.remove(&msg.id);
+        let game_over = GameOver {
+            winner,
+            scores: self.scores.clone(),
+        };
+
+        self.scores.values_mut().for_each(|score| *score = 0);
+
+        Some(game_over)
     }
-}
 
-impl Handler<WrappedConversation<Vec<f32>>> for Game {
-    type Result = ();
+    /// Leaves an explosion where `bullet` is; the bullet itself must already be gone.
+    fn detonate(&mut self, bullet: &Bullet) {
+        let explosion = Explosion::new(bullet.owner, bullet.position, bullet.damage);
+        self.entities.insert(Uuid::new_v4(), EntityKind::Explosion(explosion));
+    }
 
-    fn handle(&mut self, msg: WrappedConversation<Vec<f32>>, ctx: &mut Self::Context) -> Self::Result {
-        let mut state = match self.state.lock() {
-            Ok(state) => state,
-            Err(_) => {
-                ctx.stop();
-                return;
+    /// Hurts every player inside an explosion by its share of damage for this step.
+    fn apply_explosions(&mut self, delta: f32, config: &GameConfig) {
+        let explosions: Vec<Explosion> = self.entities
+            .values()
+            .filter_map(|entity| entity.as_explosion())
+            .cloned()
+            .collect();
+
+        for explosion in explosions {
+            let hurt: Vec<Uuid> = self.entities
+                .values()
+                .filter_map(|entity| entity.as_player())
+                .filter(|player| {
+                    let reach = explosion.radius + player.radius;
+                    player.position.distance_squared(&explosion.position) <= reach * reach
+                })
+                .filter(|player| self.can_hurt(explosion.owner, &player.id, config))
+                .map(|player| player.id)
+                .collect();
+
+            for id in hurt {
+                self.apply_hit(&id, explosion.owner, explosion.damage * delta / explosion.duration, DamageCause::Explosion);
             }
+        }
+    }
+
+    /// Shrinks the safe zone towards its minimum and hurts every player outside it.
+    fn shrink_zone(&mut self, delta: f32, config: &GameConfig) {
+        if self.phase != GamePhase::Active {
+            return;
+        }
+
+        let (zone, radius) = match (config.shrinking_zone, self.safe_zone_radius.as_mut()) {
+            (Some(zone), Some(radius)) => (zone, radius),
+            _ => return,
         };
 
-        let kind = msg.1.kind.as_str();
+        *radius = (*radius - zone.rate * delta).max(zone.min_radius);
 
-        match kind {
-            "move" => {
-                let entity = match state.entities.get_mut(&msg.0) {
-                    Some(entity) => entity,
-                    None => return,
-                };
+        let radius = *radius;
+        let center = self.world.center();
 
-                let player = match entity.as_any_mut().downcast_mut::<Player>() {
-                    Some(player) => player,
-                    None => return,
-                };
+        let outside: Vec<Uuid> = self.entities
+            .values()
+            .filter_map(EntityKind::as_player)
+            .filter(|player| player.position.distance_squared(&center) > radius * radius)
+            .map(|player| player.id)
+            .collect();
 
-                player.velocity.x += msg.1.data[0];
-                player.velocity.y += msg.1.data[1];
-            }
-            "fire" => {
-                let entity = match state.entities.get(&msg.0) {
-                    Some(entity) => entity,
-                    None => return,
-                };
+        for id in outside {
+            self.apply_hit(&id, None, zone.damage_per_second * delta, DamageCause::Zone);
+        }
+    }
 
-                let player = match entity.as_any().downcast_ref::<Player>() {
-                    Some(player) => player,
-                    None => return,
-                };
+    /// With a shrinking zone, ends the round once someone has died and at most one team is left alive.
+    fn check_last_team_standing(&self, config: &GameConfig) -> Option<GameOver> {
+        if config.shrinking_zone.is_none() || self.phase != GamePhase::Active || self.dead_players.is_empty() {
+            return None;
+        }
+
+        let mut teams = self.entities
+            .values()
+            .filter_map(|entity| entity.as_player())
+            .map(|player| player.team);
+
+        let winner = teams.next();
+
+        if teams.any(|team| Some(team) != winner) {
+            return None;
+        }
+
+        Some(GameOver {
+            winner,
+            scores: self.scores.clone(),
+        })
+    }
+
+    /// Final standings of a round cut short by the match timer. The team with more kills wins; a tie has no winner.
+    fn round_over(&self) -> RoundOver {
+        let red = self.scores.get(&Team::Red).copied().unwrap_or_default();
+        let blue = self.scores.get(&Team::Blue).copied().unwrap_or_default();
+
+        let winner = match red.cmp(&blue) {
+            std::cmp::Ordering::Greater => Some(Team::Red),
+            std::cmp::Ordering::Less => Some(Team::Blue),
+            std::cmp::Ordering::Equal => None,
+        };
+
+        let players = self.entities
+            .values()
+            .filter_map(|entity| entity.as_player())
+            .chain(self.dead_players.values())
+            .map(|player| PlayerScore {
+                id: player.id,
+                name: player.display_name(),
+                kills: player.kills,
+                deaths: player.deaths,
+            })
+            .collect();
+
+        RoundOver {
+            winner,
+            scores: self.scores.clone(),
+            players,
+        }
+    }
+
+    /// Whether the dead may come back now; a shrinking zone keeps them out until the round is over.
+    fn can_respawn(&self, config: &GameConfig) -> bool {
+        config.shrinking_zone.is_none() || self.phase != GamePhase::Active
+    }
+
+    /// Hands each pickup picked out by `extract` to the first player touching it and removes it from the map.
+    fn claim_pickups<T, R>(
+        &mut self,
+        extract: impl Fn(&EntityKind) -> Option<T>,
+        position: impl Fn(&T) -> Vector2f,
+        mut apply: impl FnMut(&mut Player, &T) -> R,
+    ) -> Vec<(Uuid, R)> {
+        let mut pickups: Vec<(Uuid, T)> = self.entities
+            .iter()
+            .filter_map(|(id, entity)| extract(entity).map(|pickup| (*id, pickup)))
+            .collect();
+
+        let mut claimed = Vec::new();
+
+        for entity in self.entities.values_mut() {
+            let player = match entity.as_player_mut() {
+                Some(player) => player,
+                None => continue,
+            };
 
-                let click_pos = Vector2f::new(msg.1.data[0], msg.1.data[1]);
-                let player_pos = player.position.clone();
-                let angle = (click_pos - player_pos.clone()).angle();
-                let velocity = Vector2f::from_angle(angle);
+            let index = pickups.iter().position(|(_, pickup)| {
+                player.position.distance_squared(&position(pickup)) <= PICKUP_RADIUS * PICKUP_RADIUS
+            });
 
-                state.entities.insert(
-                    Uuid::new_v4(),
-                    Box::new(Bullet::new(Some(msg.0), player_pos, velocity * 300.0)),
-                );
+            // Taking the pickup out here means a second player on the same spot can't also claim it.
+            if let Some(index) = index {
+                let (id, pickup) = pickups.swap_remove(index);
+                claimed.push((id, apply(player, &pickup)));
             }
-            _ => {}
+        }
+
+        for (id, _) in claimed.iter() {
+            self.entities.remove(id);
+        }
+
+        claimed
+    }
+
+    fn collect_health_packs(&mut self) -> Vec<PickupEvent> {
+        let extract = |entity: &EntityKind| match entity {
+            EntityKind::HealthPack(pack) => Some(pack.clone()),
+            _ => None,
+        };
+
+        let claimed = self.claim_pickups(extract, |pack: &HealthPack| pack.position, |player, pack| {
+            player.health = (player.health + pack.heal_amount).min(MAX_PLAYER_HEALTH);
+            (player.id, player.health)
+        });
+
+        claimed
+            .into_iter()
+            .map(|(pickup, (player, health))| PickupEvent { player, pickup, health })
+            .collect()
+    }
+
+    fn collect_speed_boosts(&mut self, config: &GameConfig) -> Vec<SpeedBoostEvent> {
+        let duration = Duration::from_secs(config.speed_boost_secs);
+        let until = Instant::now() + duration;
+
+        let extract = |entity: &EntityKind| match entity {
+            EntityKind::SpeedBoost(boost) => Some(boost.clone()),
+            _ => None,
         };
+
+        let claimed = self.claim_pickups(extract, |boost: &SpeedBoost| boost.position, |player, _| {
+            player.speed_boost_until = Some(until);
+            player.id
+        });
+
+        claimed
+            .into_iter()
+            .map(|(pickup, player)| SpeedBoostEvent {
+                player,
+                pickup,
+                duration_ms: duration.as_millis() as u64,
+            })
+            .collect()
     }
-}
\ No newline at end of file
+
+    fn resolve_walls(&mut self) {
+        let walls: Vec<(Aabb, bool)> = self.entities
+            .values()
+            .filter_map(EntityKind::as_wall)
+            .map(|wall| (wall.bounds(), wall.bounces_bullets))
+            .collect();
+
+        if walls.is_empty() {
+            return;
+        }
+
+        let mut destroyed = Vec::new();
+
+        for (id, entity) in self.entities.iter_mut() {
+            match entity {
+                EntityKind::Player(player) => {
+                    for (bounds, _) in walls.iter() {
+                        if let Some((normal, depth)) = bounds.separation(&player.bounds()) {
+                            player.position += normal * depth;
+
+                            let speed_into_wall = player.velocity.dot(&normal);
+                            if speed_into_wall < 0.0 {
+                                player.velocity = player.velocity - normal * speed_into_wall;
+                            }
+                        }
+                    }
+                }
+                EntityKind::Bullet(bullet) => {
+                    for (bounds, bounces_bullets) in walls.iter() {
+                        let (normal, depth) = match bounds.separation(&bullet.bounds()) {
+                            Some(separation) => separation,
+                            None => continue,
+                        };
+
+                        if !bounces_bullets {
+                            destroyed.push((*id, bullet.clone()));
+                            break;
+                        }
+
+                        bullet.position += normal * depth;
+                        bullet.velocity = bullet.velocity.reflect(&normal);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (id, bullet) in destroyed {
+            self.entities.remove(&id);
+
+            if bullet.explosive {
+                self.detonate(&bullet);
+            }
+        }
+    }
+
+    fn resolve_collisions(&mut self, config: &GameConfig) {
+        self.collision_index.clear();
+
+        let mut largest_radius: f32 = 0.0;
+
+        for (id, entity) in self.entities.iter() {
+            if let Some(player) = entity.as_player() {
+                self.collision_index.insert(*id, player.position);
+                largest_radius = largest_radius.max(player.radius);
+            }
+        }
+
+        let mut hits = Vec::new();
+
+        for entity in self.entities.values() {
+            let bullet = match entity.as_bullet() {
+                Some(bullet) => bullet,
+                None => continue,
+            };
+
+            let target = self.collision_index
+                .candidates(bullet.position, bullet.radius + largest_radius)
+                .into_iter()
+                .find(|player_id| {
+                    bullet.owner != Some(*player_id)
+                        && self.can_hurt(bullet.owner, player_id, config)
+                        && self.entities
+                            .get(player_id)
+                            .and_then(|entity| entity.as_player())
+                            .is_some_and(|player| bullet.hits(&player.position, player.radius))
+                });
+
+            if let Some(player_id) = target {
+                hits.push((bullet.clone(), player_id));
+            }
+        }
+
+        for (bullet, player_id) in hits {
+            self.entities.remove(&bullet.id);
+
+            if bullet.explosive {
+                self.detonate(&bullet);
+            } else {
+                self.apply_bullet_hit(&player_id, &bullet);
+            }
+        }
+    }
+
+    /// Damages the player `bullet` hit and knocks them back along its path.
+    fn apply_bullet_hit(&mut self, player_id: &Uuid, bullet: &Bullet) {
+        self.apply_hit(player_id, bullet.owner, bullet.effective_damage(), DamageCause::Bullet);
+
+        if let Some(player) = self.entities.get_mut(player_id).and_then(|entity| entity.as_player_mut()) {
+            player.velocity += (bullet.velocity * KNOCKBACK_FACTOR).clamp_magnitude(MAX_KNOCKBACK);
+        }
+    }
+
+    fn apply_hit(&mut self, player_id: &Uuid, owner: Option<Uuid>, damage: f32, cause: DamageCause) {
+        if let Some(player) = self.entities.get_mut(player_id).and_then(|entity| entity.as_player_mut()) {
+            player.health -= damage;
+            player.last_hit_by = owner;
+            player.last_damage_cause = cause;
+        }
+    }
+
+    /// Inserts a fresh player for `id` unless one is already alive.
+    fn spawn_player(&mut self, id: Uuid, config: &GameConfig) -> bool {
+        if self.entities.contains_key(&id) {
+            return false;
+        }
+
+        let fallen = self.dead_players.remove(&id);
+        let team = fallen.as_ref().map_or_else(|| self.smaller_team(), |fallen| fallen.team);
+        let mut player = Player::new(id, team, self.spawn_position(config));
+
+        if let Some(fallen) = fallen {
+            player.name = fallen.name;
+            player.kills = fallen.kills;
+            player.deaths = fallen.deaths;
+            player.current_weapon = fallen.current_weapon;
+        }
+
+        self.entities.insert(id, EntityKind::Player(player));
+        true
+    }
+
+    /// The team with fewer players, counting those waiting to respawn; red on a tie.
+    fn smaller_team(&self) -> Team {
+        let (red, blue) = self.entities
+            .values()
+            .filter_map(|entity| entity.as_player())
+            .chain(self.dead_players.values())
+            .fold((0, 0), |(red, blue), player| match player.team {
+                Team::Red => (red + 1, blue),
+                Team::Blue => (red, blue + 1),
+            });
+
+        if blue < red {
+            Team::Blue
+        } else {
+            Team::Red
+        }
+    }
+
+    /// Whether a bullet fired by `owner` damages `target`, which it doesn't for teammates unless friendly fire is on.
+    fn can_hurt(&self, owner: Option<Uuid>, target: &Uuid, config: &GameConfig) -> bool {
+        config.friendly_fire || !self.same_team(owner, target)
+    }
+
+    fn same_team(&self, owner: Option<Uuid>, target: &Uuid) -> bool {
+        match owner.and_then(|owner| self.player(&owner)) {
+            Some(owner) => self.player(target).is_some_and(|target| target.team == owner.team),
+            None => false,
+        }
+    }
+
+    fn bullet_count(&self) -> usize {
+        self.entities
+            .values()
+            .filter(|entity| matches!(entity, EntityKind::Bullet(_)))
+            .count()
+    }
+
+    /// Picks the configured spawn point furthest from every living player, or a random spot if none are configured.
+    fn spawn_position(&mut self, config: &GameConfig) -> Vector2f {
+        let players: Vec<Vector2f> = self.entities
+            .values()
+            .filter_map(|entity| entity.as_player())
+            .map(|player| player.position)
+            .collect();
+
+        let nearest_player = |point: &Vector2f| {
+            players
+                .iter()
+                .map(|position| point.distance_squared(position))
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        config.spawn_points
+            .iter()
+            .max_by(|a, b| nearest_player(a).total_cmp(&nearest_player(b)))
+            .copied()
+            .unwrap_or_else(|| {
+                let rng = self.rng();
+                self.world.random_position(&rng)
+            })
+    }
+
+    /// Hands the player `old`, alive or dead, over to the session `new`.
+    /// Any reload in progress is dropped, since its timer still looks for `old`.
+    fn transfer_player(&mut self, old: &Uuid, new: Uuid) -> bool {
+        if let Some(EntityKind::Player(player)) = self.entities.get(old) {
+            let player = Player { id: new, is_reloading: false, ..player.clone() };
+            self.entities.remove(old);
+            self.entities.insert(new, EntityKind::Player(player));
+            return true;
+        }
+
+        match self.dead_players.remove(old) {
+            Some(player) => {
+                self.dead_players.insert(new, Player { id: new, is_reloading: false, ..player });
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn wall_bounds(&self) -> Vec<Aabb> {
+        self.entities
+            .values()
+            .filter_map(EntityKind::as_wall)
+            .map(Wall::bounds)
+            .collect()
+    }
+
+    fn player(&self, id: &Uuid) -> Option<&Player> {
+        match self.entities.get(id) {
+            Some(entity) => entity.as_player(),
+            None => self.dead_players.get(id),
+        }
+    }
+
+    fn player_mut(&mut self, id: &Uuid) -> Option<&mut Player> {
+        match self.entities.get_mut(id) {
+            Some(entity) => entity.as_player_mut(),
+            None => self.dead_players.get_mut(id),
+        }
+    }
+
+    /// Who a join or leave notice is about, alive or not.
+    fn presence(&self, id: Uuid) -> PlayerPresence {
+        PlayerPresence {
+            id,
+            name: self.player(&id)
+                .map(|player| player.name.clone())
+                .filter(|name| !name.is_empty()),
+        }
+    }
+
+    fn remove_dead_players(&mut self) -> Vec<PlayerDied> {
+        let dead: Vec<PlayerDied> = self.entities
+            .values()
+            .filter_map(|entity| entity.as_player())
+            .filter(|player| player.health <= 0.0)
+            .map(|player| PlayerDied {
+                victim: player.id,
+                killer: player.last_hit_by,
+                cause: player.last_damage_cause,
+            })
+            .collect();
+
+        for death in dead.iter() {
+            if let Some(EntityKind::Player(mut player)) = self.entities.remove(&death.victim) {
+                player.deaths += 1;
+                self.dead_players.insert(death.victim, player);
+            }
+        }
+
+        dead
+    }
+
+    /// Names a death for display. Deaths nobody caused, such as to the zone, are blamed on the environment.
+    fn kill_feed(&self, death: &PlayerDied) -> KillFeed {
+        let name = |id: &Uuid| self.player(id).map_or_else(|| "unknown".to_string(), Player::display_name);
+
+        KillFeed {
+            attacker: death.killer.as_ref().map_or_else(|| "environment".to_string(), name),
+            victim: name(&death.victim),
+            cause: death.cause,
+        }
+    }
+
+    /// Gives the killer credit for a death already counted against the victim.
+    /// Deaths with no owner, or whose killer has since disconnected, only count against the victim.
+    fn credit_kill(&mut self, death: &PlayerDied) -> Option<KillEvent> {
+        // Team kills, only possible with friendly fire on, don't count towards anyone's score.
+        let killer = death.killer.filter(|killer| !self.same_team(Some(*killer), &death.victim))?;
+        let deaths = self.dead_players.get(&death.victim)?.deaths;
+
+        // A killer shot down in the same volley is already waiting to respawn, but still earned the kill.
+        let player = self.player_mut(&killer)?;
+
+        player.kills += 1;
+
+        let (kills, team) = (player.kills, player.team);
+        *self.scores.entry(team).or_default() += 1;
+
+        Some(KillEvent {
+            killer,
+            victim: death.victim,
+            kills,
+            deaths,
+        })
+    }
+}
+
+type Session = Recipient<MyMessage>;
+
+pub struct Game {
+    config: GameConfig,
+    state: Arc<RwLock<GameState>>,
+    sessions: HashMap<Uuid, Session>,
+    last_fired: HashMap<Uuid, Instant>,
+    snapshots: HashMap<Uuid, Snapshot>,
+    /// Last known position of each session's player, kept while they are dead.
+    view_positions: HashMap<Uuid, Vector2f>,
+    encodings: HashMap<Uuid, Encoding>,
+    /// Highest input `seq` handled for each session.
+    last_processed_seq: HashMap<Uuid, u32>,
+    history: History,
+    /// Where to mirror broadcasts for read-only subscribers, how many there are per room, and the room to file them under.
+    events: Option<(Addr<EventBus>, SubscriberCounts, String)>,
+    /// Secret each session can present when reconnecting to take its player back.
+    tokens: HashMap<Uuid, Uuid>,
+    /// Players whose session dropped, by token, with when it happened.
+    pending_disconnects: HashMap<Uuid, (Uuid, Instant)>,
+    /// Bumped on every phase change, so timers set for an earlier phase can tell they are stale.
+    phase_epoch: u64,
+    /// This game's share of `game_entities_total` as of the last tick.
+    reported_entities: i64,
+    /// Real time not yet simulated, always less than a few `FIXED_DT`.
+    accumulator: f32,
+    tick: u64,
+    /// When the last tick ran, on the runtime's clock so tests can pause it.
+    start_time: actix::clock::Instant,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(GameConfig::default())
+    }
+}
+
+impl Game {
+    pub fn new(config: GameConfig) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(GameState::new(&config))),
+            config,
+            sessions: HashMap::new(),
+            last_fired: HashMap::new(),
+            snapshots: HashMap::new(),
+            view_positions: HashMap::new(),
+            encodings: HashMap::new(),
+            last_processed_seq: HashMap::new(),
+            history: History::new(HISTORY_LEN),
+            events: None,
+            tokens: HashMap::new(),
+            pending_disconnects: HashMap::new(),
+            phase_epoch: 0,
+            reported_entities: 0,
+            accumulator: 0.0,
+            tick: 0,
+            start_time: actix::clock::Instant::now(),
+        }
+    }
+
+    pub fn with_events(mut self, events: Addr<EventBus>, subscribers: SubscriberCounts, room: String) -> Self {
+        self.events = Some((events, subscribers, room));
+        self
+    }
+
+    /// Shared handle to the simulated state, for readers outside the actor.
+    pub fn state(&self) -> Arc<RwLock<GameState>> {
+        self.state.clone()
+    }
+
+    pub fn with_world(world: WorldBounds) -> Self {
+        Self::new(GameConfig {
+            world,
+            ..GameConfig::default()
+        })
+    }
+
+    /// A game whose random spawns are reproducible.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::new(GameConfig {
+            seed: Some(seed),
+            ..GameConfig::default()
+        })
+    }
+
+    pub fn with_tick_rate(tick_rate: Duration) -> Self {
+        Self::new(GameConfig {
+            tick_ms: (tick_rate.as_millis() as u64).max(1),
+            ..GameConfig::default()
+        })
+    }
+
+    fn encoding(&self, id: &Uuid) -> Encoding {
+        self.encodings.get(id).copied().unwrap_or_default()
+    }
+
+    /// Mirrors a broadcast to the event bus, always as JSON.
+    fn publish<T: Serialize>(&self, kind: &str, data: &T) {
+        let (events, subscribers, room) = match &self.events {
+            Some(events) => events,
+            None => return,
+        };
+
+        // Encoding the whole state every tick is wasted when nobody is listening.
+        let listening = match subscribers.read() {
+            Ok(subscribers) => subscribers.contains_key(room),
+            Err(_) => true,
+        };
+
+        if !listening {
+            return;
+        }
+
+        let event = match MyMessage::encode(kind, data, Encoding::Json).map(|msg| String::from_utf8(msg.payload)) {
+            Some(Ok(event)) => event,
+            _ => return,
+        };
+
+        events.do_send(Publish {
+            room: room.clone(),
+            event,
+        });
+    }
+
+    fn notify<T: Serialize>(&self, kind: &str, data: &T) {
+        self.publish(kind, data);
+
+        let mut encoded: HashMap<Encoding, Option<MyMessage>> = HashMap::new();
+
+        for (id, addr) in self.sessions.iter() {
+            let encoding = self.encoding(id);
+            let msg = encoded
+                .entry(encoding)
+                .or_insert_with(|| MyMessage::encode(kind, data, encoding));
+
+            if let Some(msg) = msg {
+                addr.do_send(msg.clone());
+            }
+        }
+    }
+
+    fn send<T: Serialize>(&self, id: &Uuid, kind: &str, data: &T) {
+        let addr = match self.sessions.get(id) {
+            Some(addr) => addr,
+            None => return,
+        };
+
+        if let Some(msg) = MyMessage::encode(kind, data, self.encoding(id)) {
+            addr.do_send(msg);
+        }
+    }
+
+    fn schedule_respawn(&self, id: Uuid, ctx: &mut <Self as Actor>::Context) {
+        ctx.run_later(RESPAWN_DELAY, move |act, _ctx| {
+            if !act.sessions.contains_key(&id) {
+                return;
+            }
+
+            let mut state = match act.state.write() {
+                Ok(state) => state,
+                Err(_) => return,
+            };
+
+            if !state.can_respawn(&act.config) || !state.spawn_player(id, &act.config) {
+                return;
+            }
+
+            act.send(&id, "respawn", &id);
+        });
+    }
+
+    /// Refills the player's magazine after `RELOAD_DURATION`, unless they respawned with a full one meanwhile.
+    fn schedule_reload(&self, id: Uuid, ctx: &mut <Self as Actor>::Context) {
+        ctx.run_later(RELOAD_DURATION, move |act, _ctx| {
+            let mut state = match act.state.write() {
+                Ok(state) => state,
+                Err(_) => return,
+            };
+
+            let player = match state.player_mut(&id) {
+                Some(player) if player.is_reloading => player,
+                _ => return,
+            };
+
+            player.ammo = player.max_ammo;
+            player.is_reloading = false;
+
+            act.send(&id, "reload_complete", &player.ammo);
+        });
+    }
+
+    /// Removes a dropped player for good once `reconnect_grace_secs` pass without their client coming back.
+    fn schedule_reconnect_expiry(&self, token: Uuid, ctx: &mut <Self as Actor>::Context) {
+        let grace = Duration::from_secs(self.config.reconnect_grace_secs);
+
+        ctx.run_later(grace, move |act, ctx| {
+            let id = match act.pending_disconnects.get(&token) {
+                Some((id, since)) if since.elapsed() >= grace => *id,
+                _ => return,
+            };
+
+            act.pending_disconnects.remove(&token);
+
+            let shared = act.state.clone();
+            let mut state = match shared.write() {
+                Ok(state) => state,
+                Err(_) => return,
+            };
+
+            act.remove_player(&mut state, id);
+
+            if act.sessions.is_empty() && act.pending_disconnects.is_empty() {
+                ctx.stop();
+            }
+        });
+    }
+
+    fn remove_player(&self, state: &mut GameState, id: Uuid) {
+        let left = state.presence(id);
+
+        state.entities.remove(&id);
+        state.dead_players.remove(&id);
+
+        self.notify("player_left", &left);
+    }
+
+    fn schedule_spawn(&self, delay: Duration, spawn: fn(&mut GameState, &GameConfig), ctx: &mut <Self as Actor>::Context) {
+        let epoch = self.phase_epoch;
+
+        // A round that ended since has already been reset with a full set of pickups.
+        ctx.run_later(delay, move |act, _ctx| {
+            if act.phase_epoch != epoch {
+                return;
+            }
+
+            let mut state = match act.state.write() {
+                Ok(state) => state,
+                Err(_) => return,
+            };
+
+            spawn(&mut state, &act.config);
+        });
+    }
+
+    /// Moves to `phase`, invalidating timers set for the previous one, and tells every session.
+    fn set_phase(&mut self, state: &mut GameState, phase: GamePhase) {
+        self.phase_epoch += 1;
+        state.phase = phase;
+        self.notify("phase", &phase);
+    }
+
+    /// Starts the countdown once enough players are waiting in the lobby.
+    fn check_ready(&mut self, state: &mut GameState, ctx: &mut <Self as Actor>::Context) {
+        if state.phase == GamePhase::Lobby && self.sessions.len() >= self.config.min_players {
+            self.countdown(state, Duration::from_secs(self.config.countdown_secs), ctx);
+        }
+    }
+
+    fn countdown(&mut self, state: &mut GameState, remaining: Duration, ctx: &mut <Self as Actor>::Context) {
+        if remaining.is_zero() {
+            self.set_phase(state, GamePhase::Active);
+            self.schedule_round_end(ctx);
+            return;
+        }
+
+        self.set_phase(state, GamePhase::Countdown {
+            remaining_ms: remaining.as_millis() as u64,
+        });
+
+        let step = remaining.min(COUNTDOWN_STEP);
+        let epoch = self.phase_epoch;
+
+        ctx.run_later(step, move |act, ctx| {
+            if act.phase_epoch != epoch {
+                return;
+            }
+
+            let shared = act.state.clone();
+            let mut state = match shared.write() {
+                Ok(state) => state,
+                Err(_) => return,
+            };
+
+            act.countdown(&mut state, remaining - step, ctx);
+        });
+    }
+
+    /// Ends the round once `match_duration_secs` have passed, unless it was won before then.
+    fn schedule_round_end(&self, ctx: &mut <Self as Actor>::Context) {
+        if self.config.match_duration_secs == 0 {
+            return;
+        }
+
+        let epoch = self.phase_epoch;
+
+        ctx.run_later(Duration::from_secs(self.config.match_duration_secs), move |act, ctx| {
+            if act.phase_epoch != epoch {
+                return;
+            }
+
+            let shared = act.state.clone();
+            let mut state = match shared.write() {
+                Ok(state) => state,
+                Err(_) => return,
+            };
+
+            let round_over = state.round_over();
+            tracing::info!(winner = ?round_over.winner, "round over");
+            act.notify("round_over", &round_over);
+
+            act.end_game(&mut state, round_over.winner, ctx);
+        });
+    }
+
+    /// Shows the result for `GAME_OVER_DELAY`, then resets the state and goes back to the lobby.
+    fn end_game(&mut self, state: &mut GameState, winner: Option<Team>, ctx: &mut <Self as Actor>::Context) {
+        self.set_phase(state, GamePhase::GameOver { winner });
+
+        let epoch = self.phase_epoch;
+
+        ctx.run_later(GAME_OVER_DELAY, move |act, ctx| {
+            if act.phase_epoch != epoch {
+                return;
+            }
+
+            let shared = act.state.clone();
+            let mut state = match shared.write() {
+                Ok(state) => state,
+                Err(_) => return,
+            };
+
+            state.reset(&act.config);
+            act.set_phase(&mut state, GamePhase::Lobby);
+            act.check_ready(&mut state, ctx);
+        });
+    }
+
+    /// The part of `current` a session can see, centred on its player's last known position.
+    fn visible_to(&self, id: &Uuid, current: &Snapshot, entities: &HashMap<Uuid, EntityKind>) -> Snapshot {
+        match self.view_positions.get(id) {
+            Some(center) if self.config.view_radius.is_finite() => {
+                snapshot::visible(current, entities, *center, self.config.view_radius)
+            }
+            _ => current.clone(),
+        }
+    }
+
+    /// Sends one session a full game_state of what it can see, which its following deltas build on.
+    fn send_keyframe(&mut self, id: &Uuid, state: &GameState) {
+        let visible = self.visible_to(id, &snapshot::capture(&state.entities), &state.entities);
+
+        let mut view = snapshot::View::new(state, &visible);
+        view.your_id = Some(*id);
+        view.last_processed_seq = self.last_processed_seq.get(id).copied();
+        self.send(id, "game_state", &view);
+
+        self.snapshots.insert(*id, visible);
+    }
+
+    /// Sends every session either a full keyframe or the delta since its last snapshot, limited to what it can see.
+    fn broadcast_state(&mut self, state: &GameState) {
+        let current = snapshot::capture(&state.entities);
+
+        let keyframe = self.tick.is_multiple_of(KEYFRAME_INTERVAL);
+        self.tick += 1;
+
+        for id in self.sessions.keys() {
+            if let Some(player) = state.entities.get(id).and_then(|entity| entity.as_player()) {
+                self.view_positions.insert(*id, player.position);
+            }
+        }
+
+        self.publish("game_state", state);
+
+        let empty = Snapshot::new();
+
+        for (id, addr) in self.sessions.iter() {
+            let visible = self.visible_to(id, &current, &state.entities);
+            let encoding = self.encodings.get(id).copied().unwrap_or_default();
+            let last_processed_seq = self.last_processed_seq.get(id).copied();
+
+            let msg = if keyframe {
+                let mut view = snapshot::View::new(state, &visible);
+                view.your_id = Some(*id);
+                view.last_processed_seq = last_processed_seq;
+                MyMessage::encode("game_state", &view, encoding)
+            } else {
+                let mut delta = snapshot::diff(state.ts, self.snapshots.get(id).unwrap_or(&empty), &visible);
+                delta.last_processed_seq = last_processed_seq;
+                MyMessage::encode("game_delta", &delta, encoding)
+            };
+
+            if let Some(msg) = msg {
+                addr.do_send(msg);
+            }
+
+            self.snapshots.insert(*id, visible);
+        }
+    }
+
+    /// Tells sessions what happened during a step and schedules what follows from it.
+    fn dispatch(&mut self, events: TickEvents, ctx: &mut <Self as Actor>::Context) {
+        for death in events.deaths {
+            self.notify("player_died", &death);
+
+            self.schedule_respawn(death.victim, ctx);
+        }
+
+        for kill in events.kills {
+            self.notify("kill_event", &kill);
+        }
+
+        for entry in events.kill_feed {
+            self.notify("kill_feed", &entry);
+        }
+
+        for pickup in events.pickups {
+            self.notify("pickup", &pickup);
+
+            let delay = Duration::from_secs(self.config.health_pack_respawn_secs);
+            self.schedule_spawn(delay, GameState::spawn_health_pack, ctx);
+        }
+
+        for boost in events.speed_boosts {
+            self.notify("speed_boost", &boost);
+
+            let delay = Duration::from_secs(self.config.speed_boost_respawn_secs);
+            self.schedule_spawn(delay, GameState::spawn_speed_boost, ctx);
+        }
+
+        if let Some(game_over) = events.game_over {
+            tracing::info!(winner = ?game_over.winner, "game over");
+            self.notify("game_over", &game_over);
+
+            let shared = self.state.clone();
+            let mut state = match shared.write() {
+                Ok(state) => state,
+                Err(_) => return,
+            };
+
+            self.end_game(&mut state, game_over.winner, ctx);
+        }
+    }
+
+    fn start_ticker(&mut self, ctx: &mut <Self as Actor>::Context) {
+        self.start_time = actix::clock::Instant::now();
+
+        ctx.run_interval(Duration::from_millis(self.config.tick_ms), |act, ctx| {
+            let current_time = actix::clock::Instant::now();
+            let delta = current_time.duration_since(act.start_time).as_secs_f32();
+            act.start_time = current_time;
+
+            let steps = {
+                let mut state = match act.state.write() {
+                    Ok(state) => state,
+                    Err(_) => return,
+                };
+
+                act.accumulator += delta;
+
+                let mut steps = Vec::new();
+
+                while act.accumulator >= FIXED_DT && steps.len() < MAX_STEPS_PER_TICK {
+                    act.accumulator -= FIXED_DT;
+
+                    let events = state.step(FIXED_DT, &act.config);
+                    let game_over = events.game_over.is_some();
+                    steps.push(events);
+
+                    // The phase only changes once the events are dispatched, so further steps could end the game again.
+                    if game_over {
+                        break;
+                    }
+                }
+
+                act.accumulator = act.accumulator.min(FIXED_DT);
+                steps
+            };
+
+            for events in steps {
+                act.dispatch(events, ctx);
+            }
+
+            let shared = act.state.clone();
+            let state = match shared.read() {
+                Ok(state) => state,
+                Err(_) => return,
+            };
+
+            act.broadcast_state(&state);
+            act.history.record(state.ts, &state.entities);
+
+            let entities = state.entities.len() as i64;
+            metrics::ENTITIES_TOTAL.add(entities - act.reported_entities);
+            act.reported_entities = entities;
+            metrics::TICK_DURATION_SECONDS.observe(current_time.elapsed().as_secs_f64());
+
+            tracing::debug!(
+                entities = state.entities.len(),
+                tick_duration_us = current_time.elapsed().as_micros() as u64,
+                "tick",
+            );
+        });
+    }
+
+    /// Applies one player's input, returning whether it was acted on rather than dropped.
+    fn apply_input(&mut self, state: &mut GameState, id: Uuid, input: ClientInput, ctx: &mut <Self as Actor>::Context) -> bool {
+        match input {
+            ClientInput::Move { dx, dy } => {
+                if state.phase != GamePhase::Active {
+                    return false;
+                }
+
+                let player = match state.entities.get_mut(&id) {
+                    Some(EntityKind::Player(player)) => player,
+                    _ => return false,
+                };
+
+                // Clamping can't tame NaN or infinity, which would otherwise spread into the player's position.
+                if !dx.is_finite() || !dy.is_finite() {
+                    return false;
+                }
+
+                player.thrust = Vector2f::new(dx, dy).clamp_magnitude(1.0);
+            }
+            ClientInput::Fire { x, y, ts } => {
+                if state.phase != GamePhase::Active {
+                    return false;
+                }
+
+                let now = Instant::now();
+
+                if let Some(last_fired) = self.last_fired.get(&id) {
+                    if now.duration_since(*last_fired) < FIRE_COOLDOWN {
+                        return false;
+                    }
+                }
+
+                let bullets_alive = state.bullet_count();
+                let click_pos = Vector2f::new(x, y);
+
+                // Clicks come from the canvas, so a target far off the map can only be a tampered client.
+                if !x.is_finite() || !y.is_finite() || !state.world.is_near(click_pos) {
+                    return false;
+                }
+
+                let player = match state.entities.get_mut(&id) {
+                    Some(EntityKind::Player(player)) => player,
+                    _ => return false,
+                };
+
+                if player.is_reloading {
+                    return false;
+                }
+
+                if player.ammo == 0 {
+                    self.send(&id, "no_ammo", &());
+                    return false;
+                }
+
+                let player_pos = player.position;
+                let weapon = player.current_weapon;
+                let direction = (click_pos - player_pos).normalize_or(Vector2f::new(1.0, 0.0));
+                let velocities = weapon.velocities(direction, self.config.bullet_speed);
+
+                // A shotgun blast is fired whole or not at all, so its pellets can't push the count past the cap.
+                if bullets_alive + velocities.len() > self.config.max_bullets {
+                    return false;
+                }
+
+                player.ammo -= 1;
+
+                let walls = state.wall_bounds();
+
+                self.last_fired.insert(id, now);
+
+                for velocity in velocities {
+                    let mut bullet = Bullet::new(Some(id), player_pos, velocity).with_damage(weapon.damage());
+
+                    if let Some(fuse) = weapon.fuse() {
+                        bullet = bullet.with_fuse(fuse);
+                    }
+
+                    // Grenades are slow enough that there is nothing to gain from rewinding them.
+                    if let Some(ts) = ts.filter(|_| !bullet.explosive) {
+                        if let Some(victim) = self.history.rewind(&mut bullet, ts, &walls, |other| !state.can_hurt(Some(id), other, &self.config)) {
+                            state.apply_bullet_hit(&victim, &bullet);
+                            continue;
+                        }
+                    }
+
+                    state.entities.insert(bullet.id, EntityKind::Bullet(bullet));
+                }
+            }
+            ClientInput::Respawn => {
+                if !state.can_respawn(&self.config) || !state.spawn_player(id, &self.config) {
+                    return false;
+                }
+
+                self.send(&id, "respawn", &id);
+            }
+            ClientInput::Reload => {
+                let player = match state.entities.get_mut(&id) {
+                    Some(EntityKind::Player(player)) => player,
+                    _ => return false,
+                };
+
+                if player.is_reloading || player.ammo == player.max_ammo {
+                    return false;
+                }
+
+                player.is_reloading = true;
+                self.schedule_reload(id, ctx);
+            }
+            ClientInput::SwitchWeapon { weapon } => {
+                if let Some(player) = state.player_mut(&id) {
+                    player.current_weapon = weapon.clamped();
+                }
+            }
+            ClientInput::SetName { name } => {
+                if let Some(player) = state.player_mut(&id) {
+                    player.set_name(&name);
+                }
+            }
+            ClientInput::Chat { text } => {
+                let text = text.trim();
+
+                if text.is_empty() || text.chars().count() > MAX_CHAT_LEN {
+                    return false;
+                }
+
+                let chat = ChatEvent {
+                    sender: state.presence(id),
+                    text: text.to_string(),
+                };
+
+                self.notify("chat_event", &chat);
+            }
+            ClientInput::QueryState => {
+                self.send_keyframe(&id, state);
+            }
+            ClientInput::QuerySelf => {
+                self.send(&id, "player_state", &state.player(&id));
+            }
+            // Sessions answer pings themselves.
+            ClientInput::Ping { .. } => {}
+        }
+
+        true
+    }
+}
+
+impl Actor for Game {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.start_ticker(ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        metrics::ENTITIES_TOTAL.sub(self.reported_entities);
+        metrics::SESSIONS_ACTIVE.sub(self.sessions.len() as i64);
+    }
+}
+
+impl Handler<Connect> for Game {
+    type Result = Result<(), ConnectError>;
+
+    fn handle(&mut self, msg: Connect, ctx: &mut Self::Context) -> Self::Result {
+        // Players waiting to reconnect keep their slot, so only they can take it back.
+        let reclaiming = msg.token.is_some_and(|token| self.pending_disconnects.contains_key(&token));
+
+        if !reclaiming && self.sessions.len() + self.pending_disconnects.len() >= self.config.max_players {
+            tracing::warn!(session = %msg.id, "server full");
+            return Err(ConnectError::ServerFull);
+        }
+
+        let shared = self.state.clone();
+        let mut state = match shared.write() {
+            Ok(state) => state,
+            Err(_) => {
+                ctx.stop();
+                return Err(ConnectError::Unavailable);
+            },
+        };
+
+        let reclaimed = msg.token
+            .and_then(|token| self.pending_disconnects.remove(&token).map(|(old, _)| (token, old, state.presence(old))))
+            .filter(|(_, old, _)| state.transfer_player(old, msg.id));
+
+        let token = match reclaimed {
+            Some((token, old, left)) => {
+                tracing::info!(session = %msg.id, previous = %old, "player reconnected");
+                self.notify("player_left", &left);
+
+                // Their respawn was skipped while nobody was connected to receive it.
+                if state.dead_players.contains_key(&msg.id) {
+                    self.schedule_respawn(msg.id, ctx);
+                }
+
+                token
+            }
+            None => {
+                state.spawn_player(msg.id, &self.config);
+                Uuid::new_v4()
+            }
+        };
+
+        self.tokens.insert(msg.id, token);
+
+        let joined = state.presence(msg.id);
+        self.notify("player_joined", &joined);
+
+        self.sessions.insert(msg.id, msg.addr);
+        metrics::SESSIONS_ACTIVE.inc();
+        tracing::info!(session = %msg.id, players = self.sessions.len(), "player joined");
+
+        if let Some(encoding) = msg.encoding {
+            self.encodings.insert(msg.id, encoding);
+        }
+
+        self.send(&msg.id, "welcome", &msg.id);
+        self.send(&msg.id, "reconnect_token", &token);
+
+        if let Some(player) = state.player(&msg.id) {
+            self.notify("team_assigned", &TeamAssigned {
+                id: msg.id,
+                team: player.team,
+            });
+        }
+
+        if let Some(player) = state.entities.get(&msg.id).and_then(|entity| entity.as_player()) {
+            self.view_positions.insert(msg.id, player.position);
+        }
+
+        self.send_keyframe(&msg.id, &state);
+
+        self.check_ready(&mut state, ctx);
+
+        Ok(())
+    }
+}
+
+impl Handler<Disconnect> for Game {
+    type Result = ();
+
+    fn handle(&mut self, msg: Disconnect, ctx: &mut Self::Context) -> Self::Result {
+        let shared = self.state.clone();
+        let mut state = match shared.write() {
+            Ok(state) => state,
+            Err(_) => {
+                ctx.stop();
+                return;
+            }
+        };
+
+        if self.sessions.remove(&msg.id).is_none() {
+            return;
+        }
+
+        metrics::SESSIONS_ACTIVE.dec();
+        tracing::info!(session = %msg.id, players = self.sessions.len(), "player left");
+
+        self.last_fired.remove(&msg.id);
+        self.snapshots.remove(&msg.id);
+        self.view_positions.remove(&msg.id);
+        self.encodings.remove(&msg.id);
+        self.last_processed_seq.remove(&msg.id);
+
+        let token = self.tokens
+            .remove(&msg.id)
+            .filter(|_| self.config.reconnect_grace_secs > 0 && state.player(&msg.id).is_some());
+
+        match token {
+            Some(token) => {
+                self.pending_disconnects.insert(token, (msg.id, Instant::now()));
+                self.schedule_reconnect_expiry(token, ctx);
+            }
+            None => self.remove_player(&mut state, msg.id),
+        }
+
+        if matches!(state.phase, GamePhase::Countdown { .. }) && self.sessions.len() < self.config.min_players {
+            self.set_phase(&mut state, GamePhase::Lobby);
+        }
+
+        if self.sessions.is_empty() && self.pending_disconnects.is_empty() {
+            ctx.stop();
+        }
+    }
+}
+
+impl Handler<Ping> for Game {
+    type Result = PingResponse;
+
+    fn handle(&mut self, _msg: Ping, _ctx: &mut Self::Context) -> Self::Result {
+        PingResponse {
+            player_count: self.sessions.len(),
+        }
+    }
+}
+
+impl Handler<SetEncoding> for Game {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetEncoding, _ctx: &mut Self::Context) -> Self::Result {
+        if self.sessions.contains_key(&msg.id) {
+            self.encodings.insert(msg.id, msg.encoding);
+        }
+    }
+}
+
+impl Handler<WrappedInput> for Game {
+    type Result = ();
+
+    fn handle(&mut self, msg: WrappedInput, ctx: &mut Self::Context) -> Self::Result {
+        let shared = self.state.clone();
+        let mut state = match shared.write() {
+            Ok(state) => state,
+            Err(_) => {
+                ctx.stop();
+                return;
+            }
+        };
+
+        let WrappedInput(id, InputFrame { input, seq }) = msg;
+
+        // Inputs that were dropped haven't been seen by the game, so clients mustn't stop replaying them.
+        if self.apply_input(&mut state, id, input, ctx) {
+            if let Some(seq) = seq {
+                let last = self.last_processed_seq.entry(id).or_default();
+                *last = (*last).max(seq);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ShrinkingZone;
+    use std::sync::Mutex;
+    use actix::Addr;
+    use crate::message::{Conversation, InputFrame};
+
+    fn quiet_config() -> GameConfig {
+        GameConfig {
+            health_packs: 0,
+            speed_boosts: 0,
+            ..GameConfig::default()
+        }
+    }
+
+    #[test]
+    fn idle_player_slows_down_every_step() {
+        let config = quiet_config();
+        let mut player = Player::new(Uuid::new_v4(), Team::Red, Vector2f::new(400.0, 300.0));
+        player.velocity = Vector2f::new(200.0, 0.0);
+        let mut speed = player.velocity.magnitude();
+
+        for _ in 0..120 {
+            player.update(1.0 / 60.0, &config);
+            let slower = player.velocity.magnitude();
+            assert!(slower < speed);
+            speed = slower;
+        }
+
+        assert!(speed < 1.0);
+    }
+
+    #[test]
+    fn bullet_is_removed_once_its_lifetime_runs_out() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let bullet = Bullet::new(None, Vector2f::new(400.0, 300.0), Vector2f::new(1.0, 0.0)).with_lifetime(0.1);
+        let id = bullet.id;
+        state.entities.insert(id, EntityKind::Bullet(bullet));
+
+        state.step(1.0 / 60.0, &config);
+        assert!(state.entities.contains_key(&id));
+
+        for _ in 0..6 {
+            state.step(1.0 / 60.0, &config);
+        }
+
+        assert!(!state.entities.contains_key(&id));
+    }
+
+    #[test]
+    fn bullet_leaving_the_map_is_removed() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let bullet = Bullet::new(None, Vector2f::new(config.world.width - 5.0, 100.0), Vector2f::new(600.0, 0.0));
+        let id = bullet.id;
+        state.entities.insert(id, EntityKind::Bullet(bullet));
+
+        let mut steps = 0;
+
+        while state.entities.contains_key(&id) {
+            state.step(1.0 / 60.0, &config);
+            steps += 1;
+            assert!(steps < 10, "bullet still alive after leaving the map");
+        }
+    }
+
+    /// Stands in for a session, keeping every conversation the game sends it.
+    struct Recorder(Arc<Mutex<Vec<Conversation<String>>>>);
+
+    impl Actor for Recorder {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<MyMessage> for Recorder {
+        type Result = ();
+
+        fn handle(&mut self, msg: MyMessage, _ctx: &mut Self::Context) -> Self::Result {
+            if let Ok(conversation) = serde_json::from_slice(&msg.payload) {
+                self.0.lock().unwrap().push(conversation);
+            }
+        }
+    }
+
+    type Received = Arc<Mutex<Vec<Conversation<String>>>>;
+
+    /// A running game whose round starts as soon as someone joins, with a handle to its state.
+    fn start_game(config: GameConfig) -> (Addr<Game>, Arc<RwLock<GameState>>) {
+        let game = Game::new(GameConfig { countdown_secs: 0, ..config });
+        let state = game.state.clone();
+        (game.start(), state)
+    }
+
+    /// Connects a session that records what it is sent.
+    async fn connect(game: &Addr<Game>) -> (Uuid, Received) {
+        let (id, received, result) = try_connect(game, None).await;
+        result.unwrap();
+        (id, received)
+    }
+
+    /// Connects a recording session, reclaiming the player behind `token` if there is one.
+    async fn try_connect(game: &Addr<Game>, token: Option<Uuid>) -> (Uuid, Received, Result<(), ConnectError>) {
+        let received = Received::default();
+        let addr = Recorder(received.clone()).start();
+        let id = Uuid::new_v4();
+
+        let result = game.send(Connect { id, addr: addr.recipient(), encoding: None, token }).await.unwrap();
+
+        (id, received, result)
+    }
+
+    async fn send_input(game: &Addr<Game>, id: Uuid, input: ClientInput) {
+        game.send(WrappedInput(id, InputFrame { input, seq: None })).await.unwrap();
+    }
+
+    /// Lets the game run a few ticks and deliver what they sent.
+    async fn settle() {
+        actix::clock::sleep(Duration::from_millis(100)).await;
+    }
+
+    fn received_kind(received: &Received, kind: &str) -> Vec<String> {
+        received.lock().unwrap().iter().filter(|c| c.kind == kind).map(|c| c.data.clone()).collect()
+    }
+
+    #[actix::test]
+    async fn dead_player_leaves_state_but_keeps_its_session() {
+        let (game, state) = start_game(quiet_config());
+        let (id, received) = connect(&game).await;
+
+        state.write().unwrap().player_mut(&id).unwrap().health = 0.0;
+        settle().await;
+
+        assert!(!state.read().unwrap().entities.contains_key(&id));
+        assert_eq!(game.send(Ping).await.unwrap().player_count, 1);
+        assert_eq!(received_kind(&received, "player_died").len(), 1);
+    }
+
+    #[test]
+    fn bullet_hitting_a_player_is_removed() {
+        let mut state = GameState::new(&quiet_config());
+        let id = Uuid::new_v4();
+        let player = Player::new(id, Team::Red, Vector2f::new(400.0, 300.0));
+        let bullet = Bullet::new(None, player.position, Vector2f::new(0.0, 0.0));
+        state.entities.insert(id, EntityKind::Player(player));
+        state.entities.insert(bullet.id, EntityKind::Bullet(bullet));
+
+        state.resolve_collisions(&quiet_config());
+
+        let player = state.player(&id).unwrap();
+        assert_eq!(player.health, 100.0 - BULLET_DAMAGE);
+        assert_eq!(state.entities.len(), 1);
+    }
+
+    /// Puts a player of `team` with `health` left at `position`.
+    fn add_player(state: &mut GameState, team: Team, position: Vector2f, health: f32) -> Uuid {
+        let id = Uuid::new_v4();
+        let mut player = Player::new(id, team, position);
+        player.health = health;
+        state.entities.insert(id, EntityKind::Player(player));
+        id
+    }
+
+    /// Drops a motionless bullet right on `target`.
+    fn shoot(state: &mut GameState, owner: Option<Uuid>, target: Vector2f) {
+        let bullet = Bullet::new(owner, target, Vector2f::new(0.0, 0.0));
+        state.entities.insert(bullet.id, EntityKind::Bullet(bullet));
+    }
+
+    #[test]
+    fn player_at_zero_health_is_removed_and_reported() {
+        let mut state = GameState::new(&quiet_config());
+        let shooter = add_player(&mut state, Team::Red, Vector2f::new(100.0, 100.0), 100.0);
+        let victim = add_player(&mut state, Team::Blue, Vector2f::new(400.0, 300.0), BULLET_DAMAGE);
+        shoot(&mut state, Some(shooter), Vector2f::new(400.0, 300.0));
+
+        state.resolve_collisions(&quiet_config());
+        let deaths = state.remove_dead_players();
+
+        assert!(!state.entities.contains_key(&victim));
+        assert_eq!(deaths.len(), 1);
+        assert_eq!(deaths[0].victim, victim);
+        assert_eq!(deaths[0].killer, Some(shooter));
+    }
+
+    #[actix::test]
+    async fn respawn_brings_back_a_full_health_player_once() {
+        let (game, state) = start_game(quiet_config());
+        let (id, received) = connect(&game).await;
+
+        state.write().unwrap().player_mut(&id).unwrap().health = 0.0;
+        settle().await;
+        assert!(!state.read().unwrap().entities.contains_key(&id));
+
+        send_input(&game, id, ClientInput::Respawn).await;
+        send_input(&game, id, ClientInput::Respawn).await;
+        settle().await;
+
+        let state = state.read().unwrap();
+        let player = state.player(&id).unwrap();
+        assert_eq!(player.health, 100.0);
+        assert_eq!(received_kind(&received, "respawn").len(), 1);
+    }
+
+    #[test]
+    fn pushed_player_comes_to_rest() {
+        let config = quiet_config();
+        let mut player = Player::new(Uuid::new_v4(), Team::Red, Vector2f::new(400.0, 300.0));
+        player.velocity = Vector2f::new(0.0, config.max_player_speed);
+
+        for _ in 0..300 {
+            player.update(1.0 / 60.0, &config);
+        }
+
+        let resting = player.position;
+        player.update(1.0 / 60.0, &config);
+
+        assert!(player.velocity.magnitude() < 0.01);
+        assert!(player.position.distance(&resting) < 0.001);
+    }
+
+    fn bullets_owned_by(state: &RwLock<GameState>, owner: Uuid) -> usize {
+        state.read().unwrap().entities.values().filter_map(|entity| entity.as_bullet()).filter(|bullet| bullet.owner == Some(owner)).count()
+    }
+
+    #[actix::test]
+    async fn fire_is_limited_by_cooldown() {
+        let (game, state) = start_game(quiet_config());
+        let (id, _) = connect(&game).await;
+        // Aiming at the centre keeps the bullets on the map for the whole test, wherever the player spawned.
+        let config = quiet_config();
+        let fire = || ClientInput::Fire { x: config.world.width / 2.0, y: config.world.height / 2.0, ts: None };
+
+        send_input(&game, id, fire()).await;
+        send_input(&game, id, fire()).await;
+        assert_eq!(bullets_owned_by(&state, id), 1);
+
+        actix::clock::sleep(FIRE_COOLDOWN).await;
+        send_input(&game, id, fire()).await;
+        assert_eq!(bullets_owned_by(&state, id), 2);
+    }
+
+    #[test]
+    fn kill_event_carries_the_shooters_new_tally() {
+        let mut state = GameState::new(&quiet_config());
+        let shooter = add_player(&mut state, Team::Red, Vector2f::new(100.0, 100.0), 100.0);
+        let bot = add_player(&mut state, Team::Blue, Vector2f::new(400.0, 300.0), BULLET_DAMAGE);
+        shoot(&mut state, Some(shooter), Vector2f::new(400.0, 300.0));
+
+        state.resolve_collisions(&quiet_config());
+        let deaths = state.remove_dead_players();
+
+        let kill = state.credit_kill(&deaths[0]).unwrap();
+        assert_eq!((kill.killer, kill.victim), (shooter, bot));
+        assert_eq!((kill.kills, kill.deaths), (1, 1));
+    }
+
+    #[test]
+    fn player_bounces_at_the_edge_of_a_custom_world() {
+        let config = GameConfig {
+            world: WorldBounds { width: 200.0, height: 100.0, ..WorldBounds::default() },
+            ..quiet_config()
+        };
+        let mut player = Player::new(Uuid::new_v4(), Team::Red, Vector2f::new(198.0, 50.0));
+        player.velocity = Vector2f::new(200.0, 0.0);
+
+        player.update(1.0 / 60.0, &config);
+
+        assert_eq!(player.position.x, 200.0);
+        assert!(player.velocity.x < 0.0);
+    }
+
+    #[actix::test]
+    async fn welcome_is_the_first_message_a_session_gets() {
+        let (game, _state) = start_game(quiet_config());
+        let (id, received) = connect(&game).await;
+        settle().await;
+
+        let first = received.lock().unwrap()[0].clone();
+        assert_eq!(first.kind, "welcome");
+        assert_eq!(serde_json::from_str::<Uuid>(&first.data).unwrap(), id);
+    }
+
+    #[test]
+    fn step_moves_entities_by_their_velocity() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let bullet = Bullet::new(None, Vector2f::new(100.0, 100.0), Vector2f::new(120.0, -60.0));
+        let bullet_id = bullet.id;
+        state.entities.insert(bullet_id, EntityKind::Bullet(bullet));
+        let player = add_player(&mut state, Team::Red, Vector2f::new(400.0, 300.0), 100.0);
+        state.player_mut(&player).unwrap().velocity = Vector2f::new(60.0, 0.0);
+
+        state.step(0.5, &config);
+
+        let bullet = state.entities[&bullet_id].as_bullet().unwrap();
+        assert_eq!((bullet.position.x, bullet.position.y), (160.0, 70.0));
+        let player = state.player(&player).unwrap();
+        assert_eq!(player.position.x, 430.0);
+    }
+
+    #[actix::test]
+    async fn bullet_cap_is_never_exceeded() {
+        let config = GameConfig { max_bullets: 2, ..quiet_config() };
+        let target = Vector2f::new(config.world.width / 2.0, config.world.height / 2.0);
+        let (game, state) = start_game(config);
+
+        // Corners away from each other's line of fire, so no bullet is used up on a hit.
+        for corner in [Vector2f::new(100.0, 100.0), Vector2f::new(700.0, 100.0), Vector2f::new(100.0, 500.0)] {
+            let (id, _) = connect(&game).await;
+            state.write().unwrap().player_mut(&id).unwrap().position = corner;
+            send_input(&game, id, ClientInput::Fire { x: target.x, y: target.y, ts: None }).await;
+
+            assert!(state.read().unwrap().bullet_count() <= 2);
+        }
+
+        assert_eq!(state.read().unwrap().bullet_count(), 2);
+    }
+
+    /// Puts a wall with its top-left corner at `position`.
+    fn add_wall(state: &mut GameState, position: Vector2f, size: Vector2f, bounces_bullets: bool) {
+        state.entities.insert(Uuid::new_v4(), EntityKind::Wall(Wall::new(position, size, bounces_bullets)));
+    }
+
+    #[test]
+    fn player_overlapping_a_wall_is_pushed_out_to_its_edge() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        add_wall(&mut state, Vector2f::new(300.0, 200.0), Vector2f::new(100.0, 200.0), false);
+        let player = add_player(&mut state, Team::Red, Vector2f::new(295.0, 300.0), 100.0);
+
+        state.step(0.0, &config);
+
+        let player = state.player(&player).unwrap();
+        assert_eq!(player.position.x, 300.0 - PLAYER_RADIUS);
+    }
+
+    #[test]
+    fn bullet_touching_a_wall_is_removed() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        add_wall(&mut state, Vector2f::new(300.0, 200.0), Vector2f::new(100.0, 200.0), false);
+        let touching = Bullet::new(None, Vector2f::new(301.0, 300.0), Vector2f::new(0.0, 0.0));
+        let clear = Bullet::new(None, Vector2f::new(280.0, 300.0), Vector2f::new(0.0, 0.0));
+        let (touching_id, clear_id) = (touching.id, clear.id);
+        state.entities.insert(touching_id, EntityKind::Bullet(touching));
+        state.entities.insert(clear_id, EntityKind::Bullet(clear));
+
+        state.step(0.0, &config);
+
+        assert!(!state.entities.contains_key(&touching_id));
+        assert!(state.entities.contains_key(&clear_id));
+    }
+
+    #[test]
+    fn bouncy_wall_reflects_bullets() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        add_wall(&mut state, Vector2f::new(300.0, 200.0), Vector2f::new(100.0, 200.0), true);
+        let bullet = Bullet::new(None, Vector2f::new(301.0, 300.0), Vector2f::new(300.0, 0.0));
+        let id = bullet.id;
+        state.entities.insert(id, EntityKind::Bullet(bullet));
+
+        state.step(0.0, &config);
+
+        let bullet = state.entities[&id].as_bullet().unwrap();
+        assert!(bullet.velocity.x < 0.0);
+        assert!(bullet.position.x <= 300.0);
+    }
+
+    #[test]
+    fn health_pack_heals_up_to_max_and_only_once() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let spot = Vector2f::new(400.0, 300.0);
+        let first = add_player(&mut state, Team::Red, spot, MAX_PLAYER_HEALTH - 5.0);
+        let second = add_player(&mut state, Team::Blue, spot, MAX_PLAYER_HEALTH - 5.0);
+        state.entities.insert(Uuid::new_v4(), EntityKind::HealthPack(HealthPack::new(spot, 25.0)));
+
+        let events = state.step(0.0, &config);
+
+        assert_eq!(events.pickups.len(), 1);
+        let pickup = &events.pickups[0];
+        assert!(pickup.player == first || pickup.player == second);
+        assert_eq!(pickup.health, MAX_PLAYER_HEALTH);
+        assert_eq!(state.player_mut(&pickup.player).unwrap().health, MAX_PLAYER_HEALTH);
+        assert!(!state.entities.values().any(|entity| matches!(entity, EntityKind::HealthPack(_))));
+    }
+
+    #[actix::test]
+    async fn connecting_session_gets_a_game_state_right_away() {
+        // With time paused no tick can run, so the game_state can only come from connecting.
+        tokio::time::pause();
+        let (game, _state) = start_game(quiet_config());
+        let (id, received) = connect(&game).await;
+        game.send(Ping).await.unwrap();
+
+        let states = received_kind(&received, "game_state");
+        assert_eq!(states.len(), 1);
+        let view: serde_json::Value = serde_json::from_str(&states[0]).unwrap();
+        assert_eq!(view["your_id"], id.to_string());
+    }
+
+    #[actix::test]
+    async fn new_player_is_placed_at_a_spawn_point() {
+        let spawn_points = vec![Vector2f::new(100.0, 100.0), Vector2f::new(700.0, 500.0)];
+        let (game, state) = start_game(GameConfig { spawn_points: spawn_points.clone(), ..quiet_config() });
+
+        let (id, _) = connect(&game).await;
+
+        let position = state.write().unwrap().player_mut(&id).unwrap().position;
+        assert!(spawn_points.iter().any(|point| point.distance(&position) < 1e-3));
+    }
+
+    #[test]
+    fn spawn_point_furthest_from_players_is_chosen() {
+        let far = Vector2f::new(700.0, 500.0);
+        let config = GameConfig { spawn_points: vec![Vector2f::new(100.0, 100.0), far], ..quiet_config() };
+        let mut state = GameState::new(&config);
+        add_player(&mut state, Team::Red, Vector2f::new(150.0, 120.0), MAX_PLAYER_HEALTH);
+
+        assert!(state.spawn_position(&config).distance(&far) < 1e-3);
+    }
+
+    #[actix::test]
+    async fn set_name_updates_the_player() {
+        let (game, state) = start_game(quiet_config());
+        let (id, _) = connect(&game).await;
+
+        send_input(&game, id, ClientInput::SetName { name: "  alice\n".to_string() }).await;
+
+        assert_eq!(state.write().unwrap().player_mut(&id).unwrap().name, "alice");
+    }
+
+    #[test]
+    fn over_long_name_is_truncated() {
+        let mut player = Player::new(Uuid::new_v4(), Team::Red, Vector2f::new(0.0, 0.0));
+
+        player.set_name(&"x".repeat(MAX_NAME_LEN * 2));
+
+        assert_eq!(player.name.chars().count(), MAX_NAME_LEN);
+    }
+
+    #[test]
+    fn kill_counts_for_killer_and_victim() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let killer = add_player(&mut state, Team::Red, Vector2f::new(100.0, 100.0), MAX_PLAYER_HEALTH);
+        let victim = add_player(&mut state, Team::Blue, Vector2f::new(400.0, 300.0), BULLET_DAMAGE);
+        shoot(&mut state, Some(killer), Vector2f::new(400.0, 300.0));
+
+        let events = state.step(0.0, &config);
+
+        assert_eq!(events.kills.len(), 1);
+        let killer = state.player_mut(&killer).unwrap();
+        assert_eq!((killer.kills, killer.deaths), (1, 0));
+        let victim = &state.dead_players[&victim];
+        assert_eq!((victim.kills, victim.deaths), (0, 1));
+    }
+
+    #[test]
+    fn environmental_death_credits_nobody() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let victim = add_player(&mut state, Team::Blue, Vector2f::new(400.0, 300.0), BULLET_DAMAGE);
+        shoot(&mut state, None, Vector2f::new(400.0, 300.0));
+
+        let events = state.step(0.0, &config);
+
+        assert!(events.kills.is_empty());
+        assert_eq!(state.dead_players[&victim].deaths, 1);
+    }
+
+    #[test]
+    fn disconnected_killer_credits_nobody() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let victim = add_player(&mut state, Team::Blue, Vector2f::new(400.0, 300.0), BULLET_DAMAGE);
+        shoot(&mut state, Some(Uuid::new_v4()), Vector2f::new(400.0, 300.0));
+
+        let events = state.step(0.0, &config);
+
+        assert!(events.kills.is_empty());
+        assert_eq!(state.dead_players[&victim].deaths, 1);
+    }
+
+    fn presence_config() -> GameConfig {
+        GameConfig { reconnect_grace_secs: 0, ..quiet_config() }
+    }
+
+    #[actix::test]
+    async fn connect_announces_the_new_player() {
+        let (game, _state) = start_game(presence_config());
+        let (_, watcher) = connect(&game).await;
+
+        let (joined, _) = connect(&game).await;
+        settle().await;
+
+        let events = received_kind(&watcher, "player_joined");
+        assert!(events.iter().any(|data| data.contains(&joined.to_string())));
+    }
+
+    #[actix::test]
+    async fn disconnect_announces_the_leaving_player() {
+        let (game, _state) = start_game(presence_config());
+        let (_, watcher) = connect(&game).await;
+        let (leaver, _) = connect(&game).await;
+
+        game.send(Disconnect { id: leaver }).await.unwrap();
+        settle().await;
+
+        let events = received_kind(&watcher, "player_left");
+        assert_eq!(events.len(), 1);
+        assert!(events[0].contains(&leaver.to_string()));
+    }
+
+    #[actix::test]
+    async fn dead_player_leaving_is_still_announced() {
+        let (game, state) = start_game(presence_config());
+        let (_, watcher) = connect(&game).await;
+        let (leaver, _) = connect(&game).await;
+        state.write().unwrap().player_mut(&leaver).unwrap().health = 0.0;
+        settle().await;
+        assert!(!state.read().unwrap().entities.contains_key(&leaver));
+
+        game.send(Disconnect { id: leaver }).await.unwrap();
+        settle().await;
+
+        let events = received_kind(&watcher, "player_left");
+        assert_eq!(events.len(), 1);
+        assert!(events[0].contains(&leaver.to_string()));
+    }
+
+    #[actix::test]
+    async fn connection_past_max_players_is_turned_away() {
+        let (game, _state) = start_game(quiet_config());
+
+        for _ in 0..16 {
+            connect(&game).await;
+        }
+        let (_, _, result) = try_connect(&game, None).await;
+
+        assert!(matches!(result, Err(ConnectError::ServerFull)));
+        assert_eq!(game.send(Ping).await.unwrap().player_count, 16);
+    }
+
+    #[test]
+    fn kill_feed_names_both_players() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let killer = add_player(&mut state, Team::Red, Vector2f::new(100.0, 100.0), MAX_PLAYER_HEALTH);
+        let victim = add_player(&mut state, Team::Blue, Vector2f::new(400.0, 300.0), BULLET_DAMAGE);
+        state.player_mut(&killer).unwrap().set_name("alice");
+        state.player_mut(&victim).unwrap().set_name("bob");
+        shoot(&mut state, Some(killer), Vector2f::new(400.0, 300.0));
+
+        let events = state.step(0.0, &config);
+
+        assert_eq!(events.kill_feed.len(), 1);
+        let feed = &events.kill_feed[0];
+        assert_eq!((feed.attacker.as_str(), feed.victim.as_str()), ("alice", "bob"));
+        assert_eq!(feed.cause, DamageCause::Bullet);
+    }
+
+    #[test]
+    fn kill_feed_has_a_placeholder_for_environmental_kills() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let victim = add_player(&mut state, Team::Blue, Vector2f::new(400.0, 300.0), BULLET_DAMAGE);
+        state.player_mut(&victim).unwrap().set_name("bob");
+        shoot(&mut state, None, Vector2f::new(400.0, 300.0));
+
+        let events = state.step(0.0, &config);
+
+        assert_eq!(events.kill_feed[0].attacker, "environment");
+        assert_eq!(events.kill_feed[0].victim, "bob");
+    }
+
+    #[test]
+    fn state_round_trips_through_the_binary_encoding() {
+        let config = GameConfig::default();
+        let mut state = GameState::new(&config);
+        let owner = add_player(&mut state, Team::Red, Vector2f::new(100.0, 100.0), MAX_PLAYER_HEALTH);
+        shoot(&mut state, Some(owner), Vector2f::new(200.0, 100.0));
+
+        let msg = MyMessage::encode("game_state", &state, Encoding::Binary).unwrap();
+        let decoded: Conversation<GameState> = rmp_serde::from_slice(&msg.payload).unwrap();
+
+        assert_eq!(decoded.kind, "game_state");
+        assert_eq!(serde_json::to_value(&decoded.data).unwrap(), serde_json::to_value(&state).unwrap());
+    }
+
+    #[actix::test]
+    async fn move_only_steers_players() {
+        let (game, state) = start_game(GameConfig { spawn_points: vec![Vector2f::new(400.0, 300.0)], ..quiet_config() });
+        let (id, _) = connect(&game).await;
+        let bullet = Bullet::new(None, Vector2f::new(100.0, 100.0), Vector2f::new(0.0, 0.0));
+        let bullet_id = bullet.id;
+        state.write().unwrap().entities.insert(bullet_id, EntityKind::Bullet(bullet));
+
+        send_input(&game, bullet_id, ClientInput::Move { dx: 1.0, dy: 0.0 }).await;
+        send_input(&game, id, ClientInput::Move { dx: 1.0, dy: 0.0 }).await;
+
+        let state = state.read().unwrap();
+        assert_eq!(state.entities[&bullet_id].as_bullet().unwrap().velocity.x, 0.0);
+        // The thrust is spent by the next tick, which may already have run.
+        let player = state.player(&id).unwrap();
+        assert!(player.thrust.x == 1.0 || player.velocity.x > 0.0);
+    }
+
+    #[actix::test]
+    async fn snapshots_report_the_highest_applied_seq() {
+        let (game, _state) = start_game(quiet_config());
+        let (id, received) = connect(&game).await;
+
+        for seq in [1, 2, 3, 2] {
+            let input = InputFrame { input: ClientInput::Move { dx: 0.0, dy: 0.0 }, seq: Some(seq) };
+            game.send(WrappedInput(id, input)).await.unwrap();
+        }
+        settle().await;
+
+        let last = received.lock().unwrap()
+            .iter()
+            .rev()
+            .find(|c| c.kind == "game_state" || c.kind == "game_delta")
+            .map(|c| serde_json::from_str::<serde_json::Value>(&c.data).unwrap())
+            .unwrap();
+        assert_eq!(last["last_processed_seq"], 3);
+    }
+
+    #[actix::test]
+    async fn same_seed_spawns_the_first_player_at_the_same_spot() {
+        let mut positions = Vec::new();
+
+        for _ in 0..2 {
+            let game = Game::with_seed(42);
+            let state = game.state();
+            let game = game.start();
+            let (id, _) = connect(&game).await;
+            positions.push(state.read().unwrap().player(&id).unwrap().position);
+        }
+
+        assert_eq!((positions[0].x, positions[0].y), (positions[1].x, positions[1].y));
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_random_spawns() {
+        let config = GameConfig { seed: Some(7), ..quiet_config() };
+        let mut first = GameState::new(&config);
+        let mut second = GameState::new(&config);
+
+        for _ in 0..5 {
+            let (a, b) = (first.spawn_position(&config), second.spawn_position(&config));
+            assert_eq!((a.x, a.y), (b.x, b.y));
+        }
+    }
+
+    #[test]
+    fn player_running_into_a_wall_stops_flush_against_it() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        add_wall(&mut state, Vector2f::new(300.0, 200.0), Vector2f::new(100.0, 200.0), false);
+        let id = add_player(&mut state, Team::Red, Vector2f::new(250.0, 300.0), MAX_PLAYER_HEALTH);
+
+        for _ in 0..60 {
+            state.player_mut(&id).unwrap().thrust = Vector2f::new(1.0, 0.0);
+            state.step(1.0 / 60.0, &config);
+        }
+
+        let player = state.player(&id).unwrap();
+        assert_eq!(player.position.x, 300.0 - PLAYER_RADIUS);
+        assert!(player.velocity.x.abs() < 1e-4);
+    }
+
+    #[test]
+    fn bullet_aimed_at_a_wall_is_removed_on_contact() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        add_wall(&mut state, Vector2f::new(300.0, 200.0), Vector2f::new(100.0, 200.0), false);
+        let bullet = Bullet::new(None, Vector2f::new(200.0, 300.0), Vector2f::new(300.0, 0.0));
+        let id = bullet.id;
+        state.entities.insert(id, EntityKind::Bullet(bullet));
+
+        // Five steps cover 25 pixels, still short of the wall.
+        for _ in 0..5 {
+            state.step(1.0 / 60.0, &config);
+        }
+        assert!(state.entities.contains_key(&id));
+
+        for _ in 0..30 {
+            state.step(1.0 / 60.0, &config);
+        }
+        assert!(!state.entities.contains_key(&id));
+    }
+
+    #[actix::test]
+    async fn joiners_alternate_between_teams() {
+        let (game, state) = start_game(quiet_config());
+        let mut ids = Vec::new();
+
+        for _ in 0..4 {
+            ids.push(connect(&game).await.0);
+        }
+
+        let state = state.read().unwrap();
+        let red = ids.iter().filter(|id| state.player(id).unwrap().team == Team::Red).count();
+        assert_eq!(red, 2);
+    }
+
+    #[test]
+    fn friendly_bullet_deals_no_damage() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let shooter = add_player(&mut state, Team::Red, Vector2f::new(100.0, 100.0), MAX_PLAYER_HEALTH);
+        let teammate = add_player(&mut state, Team::Red, Vector2f::new(400.0, 300.0), MAX_PLAYER_HEALTH);
+        shoot(&mut state, Some(shooter), Vector2f::new(400.0, 300.0));
+
+        state.step(0.0, &config);
+
+        assert_eq!(state.player(&teammate).unwrap().health, MAX_PLAYER_HEALTH);
+    }
+
+    #[test]
+    fn reaching_the_score_limit_ends_the_game_and_resets_scores() {
+        let config = GameConfig { team_score_limit: 1, ..quiet_config() };
+        let mut state = GameState::new(&config);
+        state.phase = GamePhase::Active;
+        let killer = add_player(&mut state, Team::Red, Vector2f::new(100.0, 100.0), MAX_PLAYER_HEALTH);
+        add_player(&mut state, Team::Blue, Vector2f::new(400.0, 300.0), BULLET_DAMAGE);
+        add_player(&mut state, Team::Blue, Vector2f::new(600.0, 500.0), MAX_PLAYER_HEALTH);
+        shoot(&mut state, Some(killer), Vector2f::new(400.0, 300.0));
+
+        let events = state.step(0.0, &config);
+
+        let game_over = events.game_over.unwrap();
+        assert_eq!(game_over.winner, Some(Team::Red));
+        assert_eq!(game_over.scores[&Team::Red], 1);
+        assert_eq!(state.scores[&Team::Red], 0);
+    }
+
+    #[test]
+    fn speed_boost_raises_max_speed_until_it_runs_out() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let spot = Vector2f::new(400.0, 300.0);
+        let id = add_player(&mut state, Team::Red, spot, MAX_PLAYER_HEALTH);
+        state.entities.insert(Uuid::new_v4(), EntityKind::SpeedBoost(SpeedBoost::new(spot)));
+
+        let events = state.step(0.0, &config);
+
+        assert_eq!(events.speed_boosts.len(), 1);
+        assert_eq!(state.player(&id).unwrap().max_speed(&config), config.max_player_speed * config.speed_boost_factor);
+        assert!(!state.entities.values().any(|entity| matches!(entity, EntityKind::SpeedBoost(_))));
+
+        state.player_mut(&id).unwrap().speed_boost_until = Some(Instant::now());
+        state.step(0.0, &config);
+
+        assert_eq!(state.player(&id).unwrap().max_speed(&config), config.max_player_speed);
+    }
+
+    #[actix::test]
+    async fn round_goes_from_lobby_through_countdown_to_active() {
+        tokio::time::pause();
+        let game = Game::new(GameConfig { min_players: 2, countdown_secs: 3, ..quiet_config() });
+        let state = game.state();
+        let game = game.start();
+
+        let (first, _) = connect(&game).await;
+        assert_eq!(state.read().unwrap().phase, GamePhase::Lobby);
+        send_input(&game, first, ClientInput::Move { dx: 1.0, dy: 0.0 }).await;
+
+        connect(&game).await;
+        assert!(matches!(state.read().unwrap().phase, GamePhase::Countdown { .. }));
+        send_input(&game, first, ClientInput::Move { dx: 1.0, dy: 0.0 }).await;
+        assert_eq!(state.read().unwrap().player(&first).unwrap().thrust.x, 0.0);
+
+        actix::clock::sleep(Duration::from_secs(4)).await;
+
+        let state = state.read().unwrap();
+        assert_eq!(state.phase, GamePhase::Active);
+        assert_eq!(state.player(&first).unwrap().velocity.x, 0.0);
+    }
+
+    #[test]
+    fn zone_hurts_players_outside_it_until_one_team_is_left() {
+        let config = GameConfig {
+            shrinking_zone: Some(ShrinkingZone { rate: 0.0, min_radius: 50.0, damage_per_second: 1000.0 }),
+            ..quiet_config()
+        };
+        let mut state = GameState::new(&config);
+        state.phase = GamePhase::Active;
+        state.safe_zone_radius = Some(50.0);
+        let inside = add_player(&mut state, Team::Red, config.world.center(), MAX_PLAYER_HEALTH);
+        let outside = add_player(&mut state, Team::Blue, Vector2f::new(10.0, 10.0), 5.0);
+
+        let events = state.step(0.1, &config);
+
+        assert_eq!(state.player(&inside).unwrap().health, MAX_PLAYER_HEALTH);
+        assert!(!state.entities.contains_key(&outside));
+        assert_eq!(events.game_over.unwrap().winner, Some(Team::Red));
+    }
+
+    #[test]
+    fn joiners_fill_the_smaller_team() {
+        let mut state = GameState::new(&quiet_config());
+
+        for _ in 0..7 {
+            let team = state.smaller_team();
+            add_player(&mut state, team, Vector2f::new(100.0, 100.0), MAX_PLAYER_HEALTH);
+        }
+        let blue: Vec<Uuid> = state.entities
+            .values()
+            .filter_map(|entity| entity.as_player())
+            .filter(|player| player.team == Team::Blue)
+            .map(|player| player.id)
+            .collect();
+        assert_eq!(blue.len(), 3);
+
+        // Once two blue players leave, the next two joiners go to blue.
+        state.entities.remove(&blue[0]);
+        state.entities.remove(&blue[1]);
+        assert_eq!(state.smaller_team(), Team::Blue);
+        add_player(&mut state, Team::Blue, Vector2f::new(100.0, 100.0), MAX_PLAYER_HEALTH);
+        assert_eq!(state.smaller_team(), Team::Blue);
+    }
+
+    #[test]
+    fn friendly_fire_lets_teammates_hurt_each_other() {
+        let config = GameConfig { friendly_fire: true, ..quiet_config() };
+        let mut state = GameState::new(&config);
+        let shooter = add_player(&mut state, Team::Red, Vector2f::new(100.0, 100.0), MAX_PLAYER_HEALTH);
+        let teammate = add_player(&mut state, Team::Red, Vector2f::new(400.0, 300.0), MAX_PLAYER_HEALTH);
+        shoot(&mut state, Some(shooter), Vector2f::new(400.0, 300.0));
+
+        state.step(0.0, &config);
+
+        assert!(state.player(&teammate).unwrap().health < MAX_PLAYER_HEALTH);
+    }
+
+    #[actix::test]
+    async fn shotgun_fire_inserts_one_bullet_per_pellet() {
+        let config = GameConfig { spawn_points: vec![Vector2f::new(100.0, 100.0)], ..quiet_config() };
+        let target = config.world.center();
+        let (game, state) = start_game(config);
+        let (id, _) = connect(&game).await;
+
+        send_input(&game, id, ClientInput::SwitchWeapon { weapon: Weapon::Shotgun { pellet_count: 6 } }).await;
+        send_input(&game, id, ClientInput::Fire { x: target.x, y: target.y, ts: None }).await;
+
+        assert_eq!(bullets_owned_by(&state, id), 6);
+    }
+
+    #[actix::test]
+    async fn match_timer_ends_the_round_and_resets_it() {
+        // Paused time jumps ahead whenever the game is idle, so the timers run out without waiting for them.
+        tokio::time::pause();
+        let config = GameConfig { match_duration_secs: 1, spawn_points: vec![Vector2f::new(100.0, 100.0)], ..quiet_config() };
+        let (game, state) = start_game(config);
+        let (id, received) = connect(&game).await;
+        let mut bullet = Bullet::new(None, Vector2f::new(700.0, 500.0), Vector2f::new(0.0, 0.0));
+        bullet.lifetime = 60.0;
+        {
+            let mut state = state.write().unwrap();
+            let player = state.player_mut(&id).unwrap();
+            player.kills = 3;
+            player.health = 10.0;
+            state.entities.insert(bullet.id, EntityKind::Bullet(bullet));
+        }
+
+        actix::clock::sleep(Duration::from_millis(1100)).await;
+
+        assert_eq!(received_kind(&received, "round_over").len(), 1);
+        assert!(matches!(state.read().unwrap().phase, GamePhase::GameOver { .. }));
+
+        actix::clock::sleep(GAME_OVER_DELAY).await;
+
+        let state = state.read().unwrap();
+        let player = state.player(&id).unwrap();
+        assert_eq!((player.kills, player.health), (0, MAX_PLAYER_HEALTH));
+        assert_eq!(state.bullet_count(), 0);
+    }
+
+    #[actix::test]
+    async fn firing_with_an_empty_magazine_does_nothing() {
+        let config = GameConfig { spawn_points: vec![Vector2f::new(100.0, 100.0)], ..quiet_config() };
+        let target = config.world.center();
+        let (game, state) = start_game(config);
+        let (id, received) = connect(&game).await;
+        state.write().unwrap().player_mut(&id).unwrap().ammo = 0;
+
+        send_input(&game, id, ClientInput::Fire { x: target.x, y: target.y, ts: None }).await;
+        settle().await;
+
+        assert_eq!(bullets_owned_by(&state, id), 0);
+        assert_eq!(received_kind(&received, "no_ammo").len(), 1);
+    }
+
+    #[actix::test]
+    async fn reload_restores_a_full_magazine() {
+        tokio::time::pause();
+        let (game, state) = start_game(quiet_config());
+        let (id, received) = connect(&game).await;
+        state.write().unwrap().player_mut(&id).unwrap().ammo = 0;
+
+        send_input(&game, id, ClientInput::Reload).await;
+        assert!(state.read().unwrap().player(&id).unwrap().is_reloading);
+
+        actix::clock::sleep(RELOAD_DURATION + Duration::from_millis(100)).await;
+
+        let player = state.read().unwrap().player(&id).unwrap().clone();
+        assert_eq!(player.ammo, player.max_ammo);
+        assert!(!player.is_reloading);
+        assert_eq!(received_kind(&received, "reload_complete").len(), 1);
+    }
+
+    #[actix::test]
+    async fn queries_are_answered_only_to_the_asking_session() {
+        // Paused time keeps the ticker, and its keyframes, from running while the queries are answered.
+        tokio::time::pause();
+        let (game, _state) = start_game(quiet_config());
+        let (asking, asked) = connect(&game).await;
+        let (_, other) = connect(&game).await;
+        asked.lock().unwrap().clear();
+        other.lock().unwrap().clear();
+
+        send_input(&game, asking, ClientInput::QueryState).await;
+        send_input(&game, asking, ClientInput::QuerySelf).await;
+
+        assert_eq!(received_kind(&asked, "game_state").len(), 1);
+        let player: Player = serde_json::from_str(&received_kind(&asked, "player_state")[0]).unwrap();
+        assert_eq!(player.id, asking);
+        assert!(other.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn grenade_explosion_hurts_players_in_its_radius() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let thrower = add_player(&mut state, Team::Red, Vector2f::new(100.0, 100.0), MAX_PLAYER_HEALTH);
+        let caught = add_player(&mut state, Team::Blue, Vector2f::new(400.0, 330.0), MAX_PLAYER_HEALTH);
+        let clear = add_player(&mut state, Team::Blue, Vector2f::new(400.0, 400.0), MAX_PLAYER_HEALTH);
+        let mut grenade = Bullet::new(Some(thrower), Vector2f::new(400.0, 300.0), Vector2f::new(0.0, 0.0)).with_fuse(0.01);
+        grenade.damage = EXPLOSION_DAMAGE;
+        state.entities.insert(grenade.id, EntityKind::Bullet(grenade));
+
+        for _ in 0..60 {
+            state.step(1.0 / 60.0, &config);
+        }
+
+        assert!((state.player(&caught).unwrap().health - (MAX_PLAYER_HEALTH - EXPLOSION_DAMAGE)).abs() < 1.0);
+        assert_eq!(state.player(&clear).unwrap().health, MAX_PLAYER_HEALTH);
+        assert!(!state.entities.values().any(|entity| entity.as_explosion().is_some()));
+    }
+
+    /// The reconnect token a session was sent.
+    fn reconnect_token(received: &Received) -> Uuid {
+        serde_json::from_str(&received_kind(received, "reconnect_token")[0]).unwrap()
+    }
+
+    #[actix::test]
+    async fn reconnecting_with_the_token_reclaims_the_player() {
+        let (game, state) = start_game(quiet_config());
+        let (old, received) = connect(&game).await;
+        let token = reconnect_token(&received);
+        let team = {
+            let mut state = state.write().unwrap();
+            let player = state.player_mut(&old).unwrap();
+            player.kills = 4;
+            player.position = Vector2f::new(123.0, 456.0);
+            player.team
+        };
+        game.send(Disconnect { id: old }).await.unwrap();
+
+        let (new, received, result) = try_connect(&game, Some(token)).await;
+
+        result.unwrap();
+        let state = state.read().unwrap();
+        assert!(state.player(&old).is_none());
+        let player = state.player(&new).unwrap();
+        assert_eq!((player.kills, player.position.x, player.position.y, player.team), (4, 123.0, 456.0, team));
+        assert_eq!(reconnect_token(&received), token);
+    }
+
+    #[test]
+    fn held_thrust_speeds_a_player_up_gradually() {
+        let config = quiet_config();
+        let mut player = Player::new(Uuid::new_v4(), Team::Red, Vector2f::new(400.0, 300.0));
+        let mut speeds = Vec::new();
+
+        for _ in 0..10 {
+            player.thrust = Vector2f::new(1.0, 0.0);
+            player.update(FIXED_DT, &config);
+            speeds.push(player.velocity.magnitude());
+        }
+
+        assert!(speeds[0] < config.max_player_speed / 2.0);
+        assert!(speeds.windows(2).all(|pair| pair[1] > pair[0]));
+        assert!(speeds.iter().all(|speed| *speed <= config.max_player_speed));
+    }
+
+    #[actix::test]
+    async fn absurd_move_vector_is_bounded() {
+        let config = quiet_config();
+        let (game, state) = start_game(GameConfig { spawn_points: vec![config.world.center()], ..config });
+        let (id, _) = connect(&game).await;
+
+        send_input(&game, id, ClientInput::Move { dx: 1e9, dy: 1e9 }).await;
+        send_input(&game, id, ClientInput::Move { dx: f32::NAN, dy: 0.0 }).await;
+
+        let state = state.read().unwrap();
+        let player = state.player(&id).unwrap();
+        assert!(player.thrust.length() <= 1.0 + 1e-4);
+        assert!(player.velocity.length() <= PLAYER_ACCELERATION * FIXED_DT + 1e-2);
+        assert!(player.position.x.is_finite() && player.position.y.is_finite());
+    }
+
+    #[test]
+    fn player_speed_is_capped() {
+        let config = quiet_config();
+        let mut player = Player::new(Uuid::new_v4(), Team::Red, config.world.center());
+        player.velocity = Vector2f::new(1e9, 0.0);
+
+        player.update(FIXED_DT, &config);
+
+        assert!(player.velocity.length() <= config.max_player_speed);
+    }
+
+    #[actix::test]
+    async fn ticks_advance_entities_by_the_time_that_passed() {
+        tokio::time::pause();
+        let (game, state) = start_game(quiet_config());
+        let (player, _received) = connect(&game).await;
+        let speed = 100.0;
+        let mut bullet = Bullet::new(None, Vector2f::new(100.0, 300.0), Vector2f::new(speed, 0.0));
+        bullet.lifetime = 60.0;
+        let id = bullet.id;
+        {
+            let mut state = state.write().unwrap();
+            // Out of the bullet's path, wherever the player happened to spawn.
+            state.player_mut(&player).unwrap().position = Vector2f::new(700.0, 550.0);
+            state.entities.insert(id, EntityKind::Bullet(bullet));
+        }
+
+        actix::clock::sleep(Duration::from_secs(2)).await;
+
+        // However the ticks happened to line up, the bullet is at most a step or two off the exact distance.
+        let x = state.read().unwrap().entities[&id].as_bullet().unwrap().position.x;
+        assert!((x - (100.0 + speed * 2.0)).abs() <= speed * FIXED_DT * 2.0, "bullet at {x}");
+    }
+
+    #[test]
+    fn hit_knocks_the_victim_along_the_bullets_path() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let victim = add_player(&mut state, Team::Blue, Vector2f::new(400.0, 300.0), MAX_PLAYER_HEALTH);
+        let bullet = Bullet::new(None, Vector2f::new(400.0, 300.0), Vector2f::new(0.0, 300.0));
+        state.entities.insert(bullet.id, EntityKind::Bullet(bullet));
+
+        state.step(0.0, &config);
+
+        let velocity = state.player(&victim).unwrap().velocity;
+        assert!(velocity.y > 0.0);
+        assert!(velocity.x.abs() < 1e-4);
+    }
+
+    #[test]
+    fn bullet_loses_damage_over_distance_down_to_a_floor() {
+        let mut bullet = Bullet::new(None, Vector2f::new(0.0, 0.0), Vector2f::new(100.0, 0.0));
+        assert_eq!(bullet.effective_damage(), bullet.damage);
+
+        bullet.advance(1.0);
+        assert_eq!(bullet.traveled, 100.0);
+        assert_eq!(bullet.effective_damage(), bullet.damage);
+
+        bullet.traveled = (FALLOFF_START + FALLOFF_END) / 2.0;
+        let halfway = bullet.effective_damage();
+        assert!(halfway < bullet.damage && halfway > bullet.damage * MIN_DAMAGE_FRACTION);
+
+        bullet.traveled = FALLOFF_END * 10.0;
+        assert!((bullet.effective_damage() - bullet.damage * MIN_DAMAGE_FRACTION).abs() < 1e-4);
+    }
+
+    #[test]
+    fn far_travelled_bullet_hits_for_less() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let near = add_player(&mut state, Team::Blue, Vector2f::new(200.0, 300.0), MAX_PLAYER_HEALTH);
+        let far = add_player(&mut state, Team::Blue, Vector2f::new(600.0, 300.0), MAX_PLAYER_HEALTH);
+        shoot(&mut state, None, Vector2f::new(200.0, 300.0));
+        let mut spent = Bullet::new(None, Vector2f::new(600.0, 300.0), Vector2f::new(0.0, 0.0));
+        spent.traveled = FALLOFF_END;
+        state.entities.insert(spent.id, EntityKind::Bullet(spent));
+
+        state.step(0.0, &config);
+
+        let near = MAX_PLAYER_HEALTH - state.player(&near).unwrap().health;
+        let far = MAX_PLAYER_HEALTH - state.player(&far).unwrap().health;
+        assert_eq!(near, BULLET_DAMAGE);
+        assert!((far - BULLET_DAMAGE * MIN_DAMAGE_FRACTION).abs() < 1e-4);
+    }
+
+    #[test]
+    fn bullet_hits_only_within_the_combined_radius() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let reach = PLAYER_RADIUS + BULLET_RADIUS;
+        let grazed = add_player(&mut state, Team::Blue, Vector2f::new(200.0, 300.0), MAX_PLAYER_HEALTH);
+        let missed = add_player(&mut state, Team::Blue, Vector2f::new(600.0, 300.0), MAX_PLAYER_HEALTH);
+        shoot(&mut state, None, Vector2f::new(200.0 + reach - 0.5, 300.0));
+        shoot(&mut state, None, Vector2f::new(600.0 + reach + 0.5, 300.0));
+
+        state.step(0.0, &config);
+
+        assert!(state.player(&grazed).unwrap().health < MAX_PLAYER_HEALTH);
+        assert_eq!(state.player(&missed).unwrap().health, MAX_PLAYER_HEALTH);
+    }
+
+    #[test]
+    fn bigger_player_is_easier_to_hit() {
+        let bullet = Bullet::new(None, Vector2f::new(30.0, 0.0), Vector2f::new(0.0, 0.0));
+        let target = Vector2f::new(0.0, 0.0);
+
+        assert!(!bullet.hits(&target, PLAYER_RADIUS));
+        assert!(bullet.hits(&target, 30.0));
+    }
+
+    fn world_config(boundary: BoundaryMode) -> GameConfig {
+        GameConfig {
+            world: WorldBounds { width: 200.0, height: 100.0, boundary },
+            ..quiet_config()
+        }
+    }
+
+    /// A player about to cross the right edge of `config`'s world.
+    fn player_leaving_right(config: &GameConfig) -> Player {
+        let mut player = Player::new(Uuid::new_v4(), Team::Red, Vector2f::new(198.0, 50.0));
+        player.velocity = Vector2f::new(200.0, 0.0);
+        player.update(FIXED_DT, config);
+        player
+    }
+
+    #[test]
+    fn wrap_mode_moves_a_player_to_the_opposite_edge() {
+        let player = player_leaving_right(&world_config(BoundaryMode::Wrap));
+
+        assert!(player.position.x < 10.0);
+        assert!(player.velocity.x > 0.0);
+    }
+
+    #[test]
+    fn clamp_mode_stops_a_player_at_the_edge() {
+        let player = player_leaving_right(&world_config(BoundaryMode::Clamp));
+
+        assert_eq!(player.position.x, 200.0);
+        assert_eq!(player.velocity.x, 0.0);
+    }
+
+    #[test]
+    fn bullets_wrap_only_in_wrap_mode() {
+        for (boundary, command) in [
+            (BoundaryMode::Wrap, EntityCommand::Keep),
+            (BoundaryMode::Bounce, EntityCommand::Remove),
+            (BoundaryMode::Clamp, EntityCommand::Remove),
+        ] {
+            let mut bullet = Bullet::new(None, Vector2f::new(190.0, 50.0), Vector2f::new(0.0, -6000.0));
+
+            assert_eq!(bullet.update(FIXED_DT, &world_config(boundary)), command, "{boundary:?}");
+            if boundary == BoundaryMode::Wrap {
+                assert!((bullet.position.y - 50.0).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[actix::test]
+    async fn fire_at_a_target_off_the_map_is_ignored() {
+        let (game, state) = start_game(quiet_config());
+        let (id, _) = connect(&game).await;
+
+        for (x, y) in [(1e9, 300.0), (f32::NAN, 300.0), (400.0, f32::INFINITY)] {
+            send_input(&game, id, ClientInput::Fire { x, y, ts: None }).await;
+        }
+        assert_eq!(bullets_owned_by(&state, id), 0);
+
+        // Ignored clicks don't start the cooldown, so an honest one right after still fires.
+        send_input(&game, id, ClientInput::Fire { x: 400.0, y: 300.0, ts: None }).await;
+        assert_eq!(bullets_owned_by(&state, id), 1);
+    }
+
+    #[test]
+    fn zone_death_is_not_credited_to_an_earlier_shooter() {
+        let config = GameConfig {
+            shrinking_zone: Some(ShrinkingZone { rate: 0.0, min_radius: 50.0, damage_per_second: 1000.0 }),
+            ..quiet_config()
+        };
+        let mut state = GameState::new(&config);
+        state.phase = GamePhase::Active;
+        state.safe_zone_radius = Some(50.0);
+        let shooter = add_player(&mut state, Team::Red, config.world.center(), MAX_PLAYER_HEALTH);
+        let victim = add_player(&mut state, Team::Blue, Vector2f::new(10.0, 10.0), 5.0);
+        state.player_mut(&victim).unwrap().last_hit_by = Some(shooter);
+
+        let events = state.step(0.1, &config);
+
+        assert!(events.kills.is_empty());
+        assert_eq!(events.kill_feed[0].cause, DamageCause::Zone);
+        assert_eq!(state.player_mut(&shooter).unwrap().kills, 0);
+    }
+
+    #[actix::test]
+    async fn shotgun_blast_that_would_pass_the_bullet_cap_is_not_fired() {
+        let config = GameConfig { max_bullets: 5, spawn_points: vec![Vector2f::new(100.0, 100.0)], ..quiet_config() };
+        let target = config.world.center();
+        let (game, state) = start_game(config);
+        let (id, _) = connect(&game).await;
+        let ammo = state.read().unwrap().player(&id).unwrap().ammo;
+
+        send_input(&game, id, ClientInput::SwitchWeapon { weapon: Weapon::Shotgun { pellet_count: 6 } }).await;
+        send_input(&game, id, ClientInput::Fire { x: target.x, y: target.y, ts: None }).await;
+
+        assert_eq!(state.read().unwrap().bullet_count(), 0);
+        assert_eq!(state.read().unwrap().player(&id).unwrap().ammo, ammo);
+    }
+
+    #[test]
+    fn deserialized_state_keeps_bullets_and_explosions_going() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let victim = add_player(&mut state, Team::Blue, Vector2f::new(400.0, 300.0), MAX_PLAYER_HEALTH);
+        let bullet = Bullet::new(None, Vector2f::new(100.0, 100.0), Vector2f::new(10.0, 0.0));
+        let bullet_id = bullet.id;
+        state.entities.insert(bullet_id, EntityKind::Bullet(bullet));
+        state.entities.insert(Uuid::new_v4(), EntityKind::Explosion(Explosion::new(None, Vector2f::new(400.0, 300.0), EXPLOSION_DAMAGE)));
+
+        let json = serde_json::to_string(&state).unwrap();
+        let mut state: GameState = serde_json::from_str(&json).unwrap();
+        state.step(FIXED_DT, &config);
+
+        assert!(state.entities.contains_key(&bullet_id));
+        let health = state.player_mut(&victim).unwrap().health;
+        assert!(health.is_finite() && health < MAX_PLAYER_HEALTH);
+    }
+
+    #[test]
+    fn transferred_player_stops_reloading() {
+        let mut state = GameState::new(&quiet_config());
+        let old = add_player(&mut state, Team::Red, Vector2f::new(100.0, 100.0), MAX_PLAYER_HEALTH);
+        state.player_mut(&old).unwrap().is_reloading = true;
+        let new = Uuid::new_v4();
+
+        assert!(state.transfer_player(&old, new));
+
+        assert!(state.player(&old).is_none());
+        let player = state.player(&new).unwrap();
+        assert_eq!(player.id, new);
+        assert!(!player.is_reloading);
+    }
+
+    #[test]
+    fn transferred_dead_player_stays_dead() {
+        let config = quiet_config();
+        let mut state = GameState::new(&config);
+        let old = add_player(&mut state, Team::Red, Vector2f::new(100.0, 100.0), 0.0);
+        state.step(0.0, &config);
+        let new = Uuid::new_v4();
+
+        assert!(state.transfer_player(&old, new));
+
+        assert_eq!(state.dead_players[&new].deaths, 1);
+        assert!(!state.entities.contains_key(&new));
+    }
+
+    #[actix::test]
+    async fn player_waiting_to_reconnect_keeps_its_slot() {
+        let (game, _state) = start_game(GameConfig { max_players: 2, ..quiet_config() });
+        connect(&game).await;
+        let (dropped, received) = connect(&game).await;
+        let token = reconnect_token(&received);
+        game.send(Disconnect { id: dropped }).await.unwrap();
+
+        let (_, _, fresh) = try_connect(&game, None).await;
+        let (_, _, reclaimed) = try_connect(&game, Some(token)).await;
+
+        assert!(matches!(fresh, Err(ConnectError::ServerFull)));
+        assert!(reclaimed.is_ok());
+    }
+
+    #[actix::test]
+    async fn dropped_inputs_do_not_advance_the_processed_seq() {
+        let (game, _state) = start_game(quiet_config());
+        let (id, received) = connect(&game).await;
+
+        let applied = InputFrame { input: ClientInput::Move { dx: 1.0, dy: 0.0 }, seq: Some(1) };
+        let rejected = InputFrame { input: ClientInput::Fire { x: f32::NAN, y: 0.0, ts: None }, seq: Some(2) };
+        game.send(WrappedInput(id, applied)).await.unwrap();
+        game.send(WrappedInput(id, rejected)).await.unwrap();
+        received.lock().unwrap().clear();
+        send_input(&game, id, ClientInput::QueryState).await;
+        settle().await;
+
+        let keyframe: serde_json::Value = serde_json::from_str(&received_kind(&received, "game_state")[0]).unwrap();
+        assert_eq!(keyframe["last_processed_seq"], 1);
+    }
+
+    #[test]
+    fn highest_score_past_the_limit_wins() {
+        let config = GameConfig { team_score_limit: 2, ..quiet_config() };
+        let mut state = GameState::new(&config);
+        state.phase = GamePhase::Active;
+        state.scores.insert(Team::Red, 2);
+        state.scores.insert(Team::Blue, 3);
+
+        assert_eq!(state.check_score_limit(&config).unwrap().winner, Some(Team::Blue));
+    }
+
+    #[test]
+    fn teams_tied_past_the_limit_draw() {
+        let config = GameConfig { team_score_limit: 2, ..quiet_config() };
+        let mut state = GameState::new(&config);
+        state.phase = GamePhase::Active;
+        state.scores.insert(Team::Red, 2);
+        state.scores.insert(Team::Blue, 2);
+
+        let game_over = state.check_score_limit(&config).unwrap();
+
+        assert_eq!(game_over.winner, None);
+        assert_eq!(state.scores[&Team::Red], 0);
+    }
+
+    #[test]
+    fn seeded_rounds_lay_out_pickups_differently() {
+        let config = GameConfig { seed: Some(7), health_packs: 1, ..quiet_config() };
+        let pack_position = |state: &GameState| state.entities
+            .values()
+            .find_map(|entity| match entity {
+                EntityKind::HealthPack(pack) => Some(pack.position),
+                _ => None,
+            })
+            .unwrap();
+        let mut state = GameState::new(&config);
+        let first = pack_position(&state);
+
+        state.reset(&config);
+        let second = pack_position(&state);
+        let mut replay = GameState::new(&config);
+        replay.reset(&config);
+
+        assert!(first.distance(&second) > 0.0);
+        assert_eq!(second.distance(&pack_position(&replay)), 0.0);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+use crate::game::{GamePhase, GameState, Team, WorldBounds};
+use crate::game::entity::EntityKind;
+use crate::geometry::vector::Vector2f;
+use crate::message::GameDelta;
+
+/// Serialized entities keyed by id, as last sent to a session.
+pub type Snapshot = HashMap<Uuid, Value>;
+
+pub fn capture(entities: &HashMap<Uuid, EntityKind>) -> Snapshot {
+    entities
+        .iter()
+        .filter_map(|(id, entity)| serde_json::to_value(entity).ok().map(|value| (*id, value)))
+        .collect()
+}
+
+/// Keeps the entries whose entity comes within `radius` of `center`.
+pub fn visible(snapshot: &Snapshot, entities: &HashMap<Uuid, EntityKind>, center: Vector2f, radius: f32) -> Snapshot {
+    snapshot
+        .iter()
+        .filter(|(id, _)| {
+            entities
+                .get(id)
+                .is_some_and(|entity| entity.bounds().distance_squared_to(&center) <= radius * radius)
+        })
+        .map(|(id, value)| (*id, value.clone()))
+        .collect()
+}
+
+/// A `GameState` restricted to what one session can see; serializes with the same shape.
+#[derive(Serialize)]
+pub struct View<'a> {
+    pub ts: i64,
+    pub phase: GamePhase,
+    pub world: WorldBounds,
+    pub safe_zone_radius: Option<f32>,
+    pub entities: &'a Snapshot,
+    /// Id of the receiving session's player.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub your_id: Option<Uuid>,
+    pub scores: HashMap<Team, u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_processed_seq: Option<u32>,
+}
+
+impl<'a> View<'a> {
+    pub fn new(state: &GameState, entities: &'a Snapshot) -> Self {
+        Self {
+            ts: state.ts,
+            phase: state.phase,
+            world: state.world,
+            safe_zone_radius: state.safe_zone_radius,
+            entities,
+            your_id: None,
+            scores: state.scores.clone(),
+            last_processed_seq: None,
+        }
+    }
+}
+
+pub fn diff(ts: i64, previous: &Snapshot, current: &Snapshot) -> GameDelta {
+    let mut created = HashMap::new();
+    let mut updated = HashMap::new();
+
+    for (id, value) in current.iter() {
+        match previous.get(id) {
+            None => {
+                created.insert(*id, value.clone());
+            }
+            Some(old) if old != value => {
+                updated.insert(*id, value.clone());
+            }
+            _ => {}
+        }
+    }
+
+    let removed = previous
+        .keys()
+        .filter(|id| !current.contains_key(id))
+        .copied()
+        .collect();
+
+    GameDelta {
+        ts,
+        created,
+        updated,
+        removed,
+        last_processed_seq: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn player_at(x: f32) -> Value {
+        json!({ "kind": "Player", "position": { "x": x, "y": 100.0 } })
+    }
+
+    #[test]
+    fn stationary_entity_gives_an_empty_delta() {
+        let previous = Snapshot::from([(Uuid::new_v4(), player_at(100.0))]);
+
+        let delta = diff(0, &previous, &previous.clone());
+
+        assert!(delta.created.is_empty());
+        assert!(delta.updated.is_empty());
+        assert!(delta.removed.is_empty());
+    }
+
+    #[test]
+    fn moved_entity_gives_exactly_one_update() {
+        let id = Uuid::new_v4();
+        let previous = Snapshot::from([(id, player_at(100.0))]);
+        let current = Snapshot::from([(id, player_at(120.0))]);
+
+        let delta = diff(0, &previous, &current);
+
+        assert!(delta.created.is_empty());
+        assert!(delta.removed.is_empty());
+        assert_eq!(delta.updated.len(), 1);
+        assert!(delta.updated.contains_key(&id));
+    }
+
+    #[test]
+    fn added_and_removed_entities_show_up_in_the_delta() {
+        let (old, new) = (Uuid::new_v4(), Uuid::new_v4());
+        let previous = Snapshot::from([(old, player_at(100.0))]);
+        let current = Snapshot::from([(new, player_at(200.0))]);
+
+        let delta = diff(0, &previous, &current);
+
+        assert_eq!(delta.created.keys().collect::<Vec<_>>(), vec![&new]);
+        assert_eq!(delta.removed, vec![old]);
+        assert!(delta.updated.is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use crate::game::{BULLET_DAMAGE, EXPLOSION_DAMAGE};
+use crate::geometry::vector::Vector2f;
+
+/// Most pellets a shotgun fires per shot, whatever a client asks for.
+pub const MAX_SHOTGUN_PELLETS: u8 = 8;
+/// Angle in radians between a shotgun's outermost pellets.
+pub const SHOTGUN_SPREAD: f32 = 0.4;
+pub const SHOTGUN_PELLET_DAMAGE: f32 = 4.0;
+pub const SNIPER_DAMAGE: f32 = 40.0;
+pub const SNIPER_SPEED_FACTOR: f32 = 3.0;
+pub const GRENADE_SPEED_FACTOR: f32 = 0.4;
+/// Seconds a grenade flies before it explodes, if it hits nothing first.
+pub const GRENADE_FUSE_SECS: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Weapon {
+    #[default]
+    Pistol,
+    Shotgun { pellet_count: u8 },
+    Sniper,
+    /// Lobs a slow grenade that leaves an `Explosion` behind.
+    Grenade,
+}
+
+impl Weapon {
+    /// Keeps a weapon picked by a client within what the server allows.
+    pub fn clamped(self) -> Self {
+        match self {
+            Weapon::Shotgun { pellet_count } => Weapon::Shotgun {
+                pellet_count: pellet_count.clamp(1, MAX_SHOTGUN_PELLETS),
+            },
+            weapon => weapon,
+        }
+    }
+
+    /// Damage dealt by each bullet this weapon fires.
+    pub fn damage(&self) -> f32 {
+        match self {
+            Weapon::Pistol => BULLET_DAMAGE,
+            Weapon::Shotgun { .. } => SHOTGUN_PELLET_DAMAGE,
+            Weapon::Sniper => SNIPER_DAMAGE,
+            Weapon::Grenade => EXPLOSION_DAMAGE,
+        }
+    }
+
+    /// How long this weapon's bullets fly before exploding, for those that do.
+    pub fn fuse(&self) -> Option<f32> {
+        match self {
+            Weapon::Grenade => Some(GRENADE_FUSE_SECS),
+            _ => None,
+        }
+    }
+
+    /// Velocities of the bullets one shot fires along the unit vector `direction`.
+    pub fn velocities(&self, direction: Vector2f, bullet_speed: f32) -> Vec<Vector2f> {
+        match *self {
+            Weapon::Pistol => vec![direction * bullet_speed],
+            Weapon::Sniper => vec![direction * bullet_speed * SNIPER_SPEED_FACTOR],
+            Weapon::Grenade => vec![direction * bullet_speed * GRENADE_SPEED_FACTOR],
+            Weapon::Shotgun { pellet_count: 0 | 1 } => vec![direction * bullet_speed],
+            Weapon::Shotgun { pellet_count } => {
+                let gaps = (pellet_count - 1) as f32;
+
+                (0..pellet_count)
+                    .map(|pellet| {
+                        let angle = SHOTGUN_SPREAD * (pellet as f32 / gaps - 0.5);
+                        direction.rotate(angle) * bullet_speed
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn pistol_fires_one_bullet_at_bullet_speed() {
+        let velocities = Weapon::Pistol.velocities(Vector2f::new(1.0, 0.0), 300.0);
+
+        assert_eq!(velocities.len(), 1);
+        assert_close(velocities[0].x, 300.0);
+        assert_close(velocities[0].y, 0.0);
+    }
+
+    #[test]
+    fn sniper_fires_three_times_as_fast() {
+        let velocities = Weapon::Sniper.velocities(Vector2f::new(0.0, 1.0), 300.0);
+
+        assert_eq!(velocities.len(), 1);
+        assert_close(velocities[0].y, 300.0 * SNIPER_SPEED_FACTOR);
+    }
+
+    #[test]
+    fn shotgun_spreads_its_pellets_evenly_around_the_aim() {
+        let direction = Vector2f::new(1.0, 0.0);
+        let velocities = Weapon::Shotgun { pellet_count: 5 }.velocities(direction, 300.0);
+
+        assert_eq!(velocities.len(), 5);
+        for velocity in velocities.iter() {
+            assert_close(velocity.length(), 300.0);
+        }
+        assert_close(velocities[0].angle(), -SHOTGUN_SPREAD / 2.0);
+        assert_close(velocities[2].angle(), 0.0);
+        assert_close(velocities[4].angle(), SHOTGUN_SPREAD / 2.0);
+    }
+
+    #[test]
+    fn single_pellet_shotgun_fires_straight() {
+        let velocities = Weapon::Shotgun { pellet_count: 1 }.velocities(Vector2f::new(1.0, 0.0), 300.0);
+
+        assert_eq!(velocities.len(), 1);
+        assert_close(velocities[0].angle(), 0.0);
+    }
+
+    #[test]
+    fn clamped_keeps_pellets_within_limits() {
+        assert_eq!(Weapon::Shotgun { pellet_count: 0 }.clamped(), Weapon::Shotgun { pellet_count: 1 });
+        assert_eq!(
+            Weapon::Shotgun { pellet_count: u8::MAX }.clamped(),
+            Weapon::Shotgun { pellet_count: MAX_SHOTGUN_PELLETS },
+        );
+        assert_eq!(Weapon::Sniper.clamped(), Weapon::Sniper);
+    }
+}
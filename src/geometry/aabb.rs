@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use crate::geometry::vector::Vector2f;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Vector2f,
+    pub max: Vector2f,
+}
+
+impl Aabb {
+    pub fn new(min: Vector2f, max: Vector2f) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_center(center: Vector2f, half_extents: Vector2f) -> Self {
+        Self {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+
+    pub fn contains(&self, point: &Vector2f) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x
+            && point.y >= self.min.y && point.y <= self.max.y
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x
+            && self.min.y <= other.max.y && self.max.y >= other.min.y
+    }
+
+    /// Squared distance from `point` to the nearest point of the box, zero if it lies inside.
+    pub fn distance_squared_to(&self, point: &Vector2f) -> f32 {
+        let closest = Vector2f::new(
+            point.x.clamp(self.min.x, self.max.x),
+            point.y.clamp(self.min.y, self.max.y),
+        );
+
+        point.distance_squared(&closest)
+    }
+
+    /// Unit normal and depth of the shortest push that moves `other` out of `self`, if they overlap.
+    pub fn separation(&self, other: &Aabb) -> Option<(Vector2f, f32)> {
+        if !self.intersects(other) {
+            return None;
+        }
+
+        let pushes = [
+            (Vector2f::new(-1.0, 0.0), other.max.x - self.min.x),
+            (Vector2f::new(1.0, 0.0), self.max.x - other.min.x),
+            (Vector2f::new(0.0, -1.0), other.max.y - self.min.y),
+            (Vector2f::new(0.0, 1.0), self.max.y - other.min.y),
+        ];
+
+        pushes.into_iter().min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32, size: f32) -> Aabb {
+        Aabb::new(Vector2f::new(x, y), Vector2f::new(x + size, y + size))
+    }
+
+    #[test]
+    fn overlapping_boxes_intersect() {
+        assert!(square(0.0, 0.0, 10.0).intersects(&square(5.0, 5.0, 10.0)));
+        assert!(square(0.0, 0.0, 10.0).intersects(&square(2.0, 2.0, 2.0)));
+    }
+
+    #[test]
+    fn touching_boxes_intersect() {
+        assert!(square(0.0, 0.0, 10.0).intersects(&square(10.0, 0.0, 10.0)));
+    }
+
+    #[test]
+    fn separate_boxes_do_not_intersect() {
+        assert!(!square(0.0, 0.0, 10.0).intersects(&square(11.0, 0.0, 10.0)));
+        assert!(!square(0.0, 0.0, 10.0).intersects(&square(0.0, -11.0, 10.0)));
+    }
+
+    #[test]
+    fn separate_boxes_need_no_separation() {
+        assert!(square(0.0, 0.0, 10.0).separation(&square(20.0, 20.0, 5.0)).is_none());
+    }
+
+    #[test]
+    fn separation_pushes_along_the_shallowest_overlap() {
+        let wall = square(0.0, 0.0, 100.0);
+
+        let (normal, depth) = wall.separation(&square(-8.0, 40.0, 10.0)).unwrap();
+        assert_eq!((normal.x, normal.y, depth), (-1.0, 0.0, 2.0));
+
+        let (normal, depth) = wall.separation(&square(40.0, 95.0, 10.0)).unwrap();
+        assert_eq!((normal.x, normal.y, depth), (0.0, 1.0, 5.0));
+    }
+}
@@ -1 +1,4 @@
-pub mod vector;
\ No newline at end of file
+pub mod vector;
+pub mod aabb;
+pub mod spatial;
+pub mod quadtree;
@@ -0,0 +1,161 @@
+use uuid::Uuid;
+use crate::geometry::aabb::Aabb;
+use crate::geometry::vector::Vector2f;
+
+/// Items a node holds before it splits.
+pub const DEFAULT_CAPACITY: usize = 8;
+/// Nodes this small never split, however many items pile up in them.
+pub const DEFAULT_MIN_SIZE: f32 = 16.0;
+
+/// Region quadtree over `bounds`. Items go to the smallest node that fully contains them, so ones straddling
+/// a split, or lying outside the root, stay in the node above.
+pub struct QuadTree {
+    bounds: Aabb,
+    capacity: usize,
+    min_size: f32,
+    items: Vec<(Uuid, Aabb)>,
+    children: Option<Box<[QuadTree; 4]>>,
+}
+
+impl QuadTree {
+    pub fn new(bounds: Aabb) -> Self {
+        Self::with_limits(bounds, DEFAULT_CAPACITY, DEFAULT_MIN_SIZE)
+    }
+
+    pub fn with_limits(bounds: Aabb, capacity: usize, min_size: f32) -> Self {
+        Self {
+            bounds,
+            capacity,
+            min_size,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+
+    fn encloses(&self, bounds: &Aabb) -> bool {
+        self.bounds.contains(&bounds.min) && self.bounds.contains(&bounds.max)
+    }
+
+    pub fn insert(&mut self, id: Uuid, bounds: Aabb) {
+        if let Some(children) = self.children.as_mut() {
+            if let Some(child) = children.iter_mut().find(|child| child.encloses(&bounds)) {
+                child.insert(id, bounds);
+                return;
+            }
+        }
+
+        self.items.push((id, bounds));
+
+        if self.children.is_none() && self.items.len() > self.capacity {
+            self.split();
+        }
+    }
+
+    fn split(&mut self) {
+        let size = self.bounds.max - self.bounds.min;
+
+        if size.x / 2.0 < self.min_size || size.y / 2.0 < self.min_size {
+            return;
+        }
+
+        let half = size / 2.0;
+        let quadrant = |x: f32, y: f32| {
+            let min = self.bounds.min + Vector2f::new(half.x * x, half.y * y);
+            QuadTree::with_limits(Aabb::new(min, min + half), self.capacity, self.min_size)
+        };
+
+        self.children = Some(Box::new([quadrant(0.0, 0.0), quadrant(1.0, 0.0), quadrant(0.0, 1.0), quadrant(1.0, 1.0)]));
+
+        for (id, bounds) in std::mem::take(&mut self.items) {
+            self.insert(id, bounds);
+        }
+    }
+
+    /// Ids of every item whose bounds overlap `bounds`.
+    pub fn query(&self, bounds: &Aabb) -> Vec<Uuid> {
+        let mut found = Vec::new();
+        self.query_into(bounds, &mut found);
+        found
+    }
+
+    fn query_into(&self, bounds: &Aabb, found: &mut Vec<Uuid>) {
+        found.extend(
+            self.items
+                .iter()
+                .filter(|(_, item)| item.intersects(bounds))
+                .map(|(id, _)| *id),
+        );
+
+        if let Some(children) = self.children.as_ref() {
+            for child in children.iter().filter(|child| child.bounds.intersects(bounds)) {
+                child.query_into(bounds, found);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.children = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_box(rng: &fastrand::Rng) -> Aabb {
+        // Some boxes reach past the 800x600 root, which the tree has to keep too.
+        let center = Vector2f::new(rng.f32() * 900.0 - 50.0, rng.f32() * 700.0 - 50.0);
+        Aabb::from_center(center, Vector2f::new(rng.f32() * 20.0 + 1.0, rng.f32() * 20.0 + 1.0))
+    }
+
+    fn sorted(mut ids: Vec<Uuid>) -> Vec<Uuid> {
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn query_matches_brute_force() {
+        let rng = fastrand::Rng::with_seed(1);
+        let mut tree = QuadTree::new(Aabb::new(Vector2f::new(0.0, 0.0), Vector2f::new(800.0, 600.0)));
+        let items: Vec<(Uuid, Aabb)> = (0..500).map(|_| (Uuid::new_v4(), random_box(&rng))).collect();
+        for (id, bounds) in items.iter() {
+            tree.insert(*id, *bounds);
+        }
+
+        for _ in 0..200 {
+            let area = random_box(&rng);
+            let expected: Vec<Uuid> = items
+                .iter()
+                .filter(|(_, bounds)| bounds.intersects(&area))
+                .map(|(id, _)| *id)
+                .collect();
+
+            assert_eq!(sorted(tree.query(&area)), sorted(expected));
+        }
+    }
+
+    #[test]
+    fn stacked_items_stop_splitting_at_the_minimum_size() {
+        let mut tree = QuadTree::with_limits(Aabb::new(Vector2f::new(0.0, 0.0), Vector2f::new(800.0, 600.0)), 2, 16.0);
+        let point = Aabb::from_center(Vector2f::new(100.0, 100.0), Vector2f::new(1.0, 1.0));
+        for _ in 0..100 {
+            tree.insert(Uuid::new_v4(), point);
+        }
+
+        assert_eq!(tree.query(&point).len(), 100);
+    }
+
+    #[test]
+    fn cleared_tree_finds_nothing() {
+        let root = Aabb::new(Vector2f::new(0.0, 0.0), Vector2f::new(800.0, 600.0));
+        let mut tree = QuadTree::with_limits(root, 1, 16.0);
+        for x in 0..10 {
+            tree.insert(Uuid::new_v4(), Aabb::from_center(Vector2f::new(x as f32 * 50.0, 50.0), Vector2f::new(2.0, 2.0)));
+        }
+
+        tree.clear();
+
+        assert!(tree.query(&root).is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::geometry::vector::Vector2f;
+
+pub const DEFAULT_CELL_SIZE: f32 = 64.0;
+
+/// Uniform grid bucketing ids by the cell their position falls in, so radius queries only look at nearby cells.
+/// Works with any cell size; cells around the size of a typical query keep both the cells visited and the ids checked few.
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<(Uuid, Vector2f)>>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, position: &Vector2f) -> (i32, i32) {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    pub fn insert(&mut self, id: Uuid, pos: Vector2f) {
+        let cell = self.cell(&pos);
+        self.cells.entry(cell).or_default().push((id, pos));
+    }
+
+    /// Ids inserted within `radius` of `pos`.
+    pub fn query_radius(&self, pos: Vector2f, radius: f32) -> Vec<Uuid> {
+        let (min_x, min_y) = self.cell(&Vector2f::new(pos.x - radius, pos.y - radius));
+        let (max_x, max_y) = self.cell(&Vector2f::new(pos.x + radius, pos.y + radius));
+
+        (min_x..=max_x)
+            .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(|(_, position)| position.distance_squared(&pos) <= radius * radius)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Empties every cell, keeping their allocations for the next round of inserts.
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_returns_only_nearby_ids() {
+        let mut grid = SpatialHash::new(32.0);
+        let near = Uuid::new_v4();
+        let edge = Uuid::new_v4();
+        let far = Uuid::new_v4();
+        grid.insert(near, Vector2f::new(105.0, 100.0));
+        grid.insert(edge, Vector2f::new(100.0, 120.0));
+        grid.insert(far, Vector2f::new(300.0, 300.0));
+
+        let mut found = grid.query_radius(Vector2f::new(100.0, 100.0), 20.0);
+        found.sort();
+        let mut expected = vec![near, edge];
+        expected.sort();
+
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn query_works_across_negative_cells() {
+        let mut grid = SpatialHash::new(32.0);
+        let id = Uuid::new_v4();
+        grid.insert(id, Vector2f::new(-5.0, -5.0));
+
+        assert_eq!(grid.query_radius(Vector2f::new(5.0, 5.0), 20.0), vec![id]);
+    }
+
+    #[test]
+    fn cleared_grid_finds_nothing() {
+        let mut grid = SpatialHash::default();
+        grid.insert(Uuid::new_v4(), Vector2f::new(10.0, 10.0));
+
+        grid.clear();
+
+        assert!(grid.query_radius(Vector2f::new(10.0, 10.0), 50.0).is_empty());
+    }
+
+    #[test]
+    fn query_checks_far_fewer_ids_than_the_naive_loop() {
+        let mut grid = SpatialHash::new(DEFAULT_CELL_SIZE);
+        let positions: Vec<Vector2f> = (0..1000)
+            .map(|i| Vector2f::new((i % 40) as f32 * 20.0, (i / 40) as f32 * 24.0))
+            .collect();
+        for position in positions.iter() {
+            grid.insert(Uuid::new_v4(), *position);
+        }
+        let center = Vector2f::new(400.0, 300.0);
+        let radius = 30.0;
+
+        // Everything in the cells the query visits gets a distance check.
+        let (min_x, min_y) = grid.cell(&Vector2f::new(center.x - radius, center.y - radius));
+        let (max_x, max_y) = grid.cell(&Vector2f::new(center.x + radius, center.y + radius));
+        let compared: usize = (min_x..=max_x)
+            .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+            .filter_map(|cell| grid.cells.get(&cell))
+            .map(Vec::len)
+            .sum();
+        let naive = positions.iter().filter(|p| p.distance(&center) <= radius).count();
+
+        assert_eq!(grid.query_radius(center, radius).len(), naive);
+        assert!(compared * 10 < positions.len());
+    }
+}
@@ -1,7 +1,7 @@
-use std::ops::{Mul, Sub};
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct Vector2f {
     pub x: f32,
     pub y: f32,
@@ -22,6 +22,87 @@ impl Vector2f {
     pub fn angle(&self) -> f32 {
         self.y.atan2(self.x)
     }
+
+    pub fn length(&self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn length_squared(&self) -> f32 {
+        self.x * self.x + self.y * self.y
+    }
+
+    pub fn magnitude(&self) -> f32 {
+        self.length()
+    }
+
+    pub fn magnitude_squared(&self) -> f32 {
+        self.length_squared()
+    }
+
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Scalar z-component of the 3D cross product; positive when `other` is counter-clockwise from `self`.
+    pub fn cross(&self, other: &Self) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Reflects the vector off a surface. `normal` is assumed to be unit length.
+    pub fn reflect(&self, normal: &Self) -> Self {
+        *self - *normal * (2.0 * self.dot(normal))
+    }
+
+    pub fn distance(&self, other: &Self) -> f32 {
+        self.distance_squared(other).sqrt()
+    }
+
+    pub fn distance_squared(&self, other: &Self) -> f32 {
+        (*self - *other).length_squared()
+    }
+
+    pub fn rotate(&self, angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Self {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
+
+    /// Scales the vector down to `max` length, preserving its direction.
+    pub fn clamp_magnitude(&self, max: f32) -> Self {
+        if self.length_squared() <= max * max {
+            return *self;
+        }
+
+        self.normalize() * max
+    }
+
+    /// Interpolates towards `other`. `t` is clamped to `[0.0, 1.0]`, so this never extrapolates.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        *self + (*other - *self) * t.clamp(0.0, 1.0)
+    }
+
+    /// Returns the unit vector, or the zero vector if `self` has no length.
+    pub fn normalize(&self) -> Self {
+        self.normalize_or(Self::default())
+    }
+
+    /// Returns the unit vector, or `fallback` if `self` has no length or no finite one.
+    pub fn normalize_or(&self, fallback: Self) -> Self {
+        // `hypot` avoids the overflow of squaring, so huge but finite vectors still normalize.
+        let length = self.x.hypot(self.y);
+
+        if length == 0.0 || !length.is_finite() {
+            return fallback;
+        }
+
+        Self {
+            x: self.x / length,
+            y: self.y / length,
+        }
+    }
 }
 
 impl Default for Vector2f {
@@ -30,6 +111,42 @@ impl Default for Vector2f {
     }
 }
 
+impl From<(f32, f32)> for Vector2f {
+    fn from((x, y): (f32, f32)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<[f32; 2]> for Vector2f {
+    fn from([x, y]: [f32; 2]) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<Vector2f> for (f32, f32) {
+    fn from(vector: Vector2f) -> Self {
+        (vector.x, vector.y)
+    }
+}
+
+impl Add for Vector2f {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl AddAssign for Vector2f {
+    fn add_assign(&mut self, other: Self) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
+
 impl Sub for Vector2f {
     type Output = Self;
 
@@ -50,4 +167,290 @@ impl Mul<f32> for Vector2f {
             y: self.y * scalar,
         }
     }
+}
+
+/// Dividing by `0.0` follows IEEE semantics and yields infinite or NaN components.
+impl Div<f32> for Vector2f {
+    type Output = Self;
+
+    fn div(self, scalar: f32) -> Self {
+        Self {
+            x: self.x / scalar,
+            y: self.y / scalar,
+        }
+    }
+}
+
+impl Neg for Vector2f {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vector2f, expected: Vector2f) {
+        assert!(
+            (actual.x - expected.x).abs() < 1e-5 && (actual.y - expected.y).abs() < 1e-5,
+            "{actual:?} != {expected:?}",
+        );
+    }
+
+    #[test]
+    fn length_of_zero_vector_is_zero() {
+        assert_eq!(Vector2f::new(0.0, 0.0).length(), 0.0);
+        assert_eq!(Vector2f::new(0.0, 0.0).length_squared(), 0.0);
+    }
+
+    #[test]
+    fn length_of_unit_vectors_is_one() {
+        assert_eq!(Vector2f::new(1.0, 0.0).length(), 1.0);
+        assert_eq!(Vector2f::new(0.0, -1.0).length_squared(), 1.0);
+    }
+
+    #[test]
+    fn length_ignores_component_signs() {
+        assert_eq!(Vector2f::new(-3.0, -4.0).length(), 5.0);
+        assert_eq!(Vector2f::new(3.0, -4.0).length_squared(), 25.0);
+    }
+
+    #[test]
+    fn magnitude_of_zero_vector_is_zero() {
+        assert_eq!(Vector2f::new(0.0, 0.0).magnitude(), 0.0);
+        assert_eq!(Vector2f::new(0.0, 0.0).magnitude_squared(), 0.0);
+    }
+
+    #[test]
+    fn magnitude_of_3_4_5_triangle() {
+        assert_eq!(Vector2f::new(3.0, 4.0).magnitude(), 5.0);
+        assert_eq!(Vector2f::new(3.0, 4.0).magnitude_squared(), 25.0);
+    }
+
+    #[test]
+    fn normalize_gives_unit_length() {
+        assert_close(Vector2f::new(3.0, 4.0).normalize(), Vector2f::new(0.6, 0.8));
+    }
+
+    #[test]
+    fn normalize_zero_vector_is_zero() {
+        assert_close(Vector2f::default().normalize(), Vector2f::default());
+    }
+
+    #[test]
+    fn normalize_or_falls_back_for_zero_vector() {
+        let fallback = Vector2f::new(0.0, 1.0);
+
+        assert_close(Vector2f::default().normalize_or(fallback), fallback);
+    }
+
+    #[test]
+    fn normalized_vectors_have_unit_length() {
+        for vector in [Vector2f::new(3.0, 4.0), Vector2f::new(-0.001, 7.0), Vector2f::new(1e6, -2e6)] {
+            assert!((vector.normalize().length() - 1.0).abs() <= f32::EPSILON, "{vector:?}");
+        }
+    }
+
+    #[test]
+    fn add_is_component_wise() {
+        assert_close(Vector2f::new(1.0, 2.0) + Vector2f::new(3.0, -5.0), Vector2f::new(4.0, -3.0));
+    }
+
+    #[test]
+    fn add_assign_mutates_in_place() {
+        let mut vector = Vector2f::new(1.0, 2.0);
+        vector += Vector2f::new(0.5, 0.5);
+
+        assert_close(vector, Vector2f::new(1.5, 2.5));
+    }
+
+    #[test]
+    fn add_is_commutative() {
+        let (a, b) = (Vector2f::new(1.5, -2.0), Vector2f::new(-4.0, 8.25));
+
+        assert_close(a + b, b + a);
+    }
+
+    #[test]
+    fn adding_zero_changes_nothing() {
+        let vector = Vector2f::new(1.5, -2.0);
+
+        assert_close(vector + Vector2f::default(), vector);
+    }
+
+    #[test]
+    fn distance_between_collinear_points() {
+        let (a, b, c) = (Vector2f::new(0.0, 0.0), Vector2f::new(2.0, 0.0), Vector2f::new(5.0, 0.0));
+
+        assert_eq!(a.distance(&b) + b.distance(&c), a.distance(&c));
+        assert_eq!(a.distance_squared(&c), 25.0);
+    }
+
+    #[test]
+    fn distance_is_symmetric() {
+        let (a, b) = (Vector2f::new(1.0, -2.0), Vector2f::new(-4.0, 7.5));
+
+        assert_eq!(a.distance(&b), b.distance(&a));
+        assert_eq!(a.distance_squared(&b), b.distance_squared(&a));
+    }
+
+    #[test]
+    fn dot_of_perpendicular_vectors_is_zero() {
+        assert_eq!(Vector2f::new(2.0, 0.0).dot(&Vector2f::new(0.0, 3.0)), 0.0);
+    }
+
+    #[test]
+    fn dot_of_parallel_vectors_is_product_of_magnitudes() {
+        let (a, b) = (Vector2f::new(3.0, 4.0), Vector2f::new(6.0, 8.0));
+
+        assert_eq!(a.dot(&b), a.magnitude() * b.magnitude());
+    }
+
+    #[test]
+    fn distance_between_identical_points_is_zero() {
+        let point = Vector2f::new(-7.0, 3.5);
+
+        assert_eq!(point.distance(&point), 0.0);
+        assert_eq!(point.distance_squared(&point), 0.0);
+    }
+
+    #[test]
+    fn distance_of_hand_computed_pairs() {
+        assert_eq!(Vector2f::new(0.0, 0.0).distance(&Vector2f::new(3.0, 4.0)), 5.0);
+        assert_eq!(Vector2f::new(1.0, 1.0).distance(&Vector2f::new(-5.0, -7.0)), 10.0);
+        assert_eq!(Vector2f::new(2.0, 3.0).distance_squared(&Vector2f::new(4.0, 0.0)), 13.0);
+    }
+
+    #[test]
+    fn lerp_endpoints_and_midpoint() {
+        let (a, b) = (Vector2f::new(0.0, 10.0), Vector2f::new(4.0, -2.0));
+
+        assert_close(a.lerp(&b, 0.0), a);
+        assert_close(a.lerp(&b, 1.0), b);
+        assert_close(a.lerp(&b, 0.5), Vector2f::new(2.0, 4.0));
+    }
+
+    #[test]
+    fn lerp_clamps_t() {
+        let (a, b) = (Vector2f::new(0.0, 10.0), Vector2f::new(4.0, -2.0));
+
+        assert_close(a.lerp(&b, -1.0), a);
+        assert_close(a.lerp(&b, 2.0), b);
+    }
+
+    #[test]
+    fn rotate_by_quarter_half_and_full_turns() {
+        let vector = Vector2f::new(1.0, 2.0);
+
+        assert_close(vector.rotate(std::f32::consts::FRAC_PI_2), Vector2f::new(-2.0, 1.0));
+        assert_close(vector.rotate(std::f32::consts::PI), Vector2f::new(-1.0, -2.0));
+        assert_close(vector.rotate(std::f32::consts::TAU), vector);
+    }
+
+    #[test]
+    fn dot_detects_orthogonality() {
+        let vector = Vector2f::new(3.0, -1.5);
+
+        assert!(vector.dot(&vector.rotate(std::f32::consts::FRAC_PI_2)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn cross_is_anti_commutative() {
+        let (a, b) = (Vector2f::new(1.0, 2.0), Vector2f::new(-3.0, 0.5));
+
+        assert_eq!(a.cross(&b), -b.cross(&a));
+        assert!(Vector2f::new(1.0, 0.0).cross(&Vector2f::new(0.0, 1.0)) > 0.0);
+    }
+
+    #[test]
+    fn div_divides_each_component() {
+        assert_close(Vector2f::new(3.0, -6.0) / 3.0, Vector2f::new(1.0, -2.0));
+    }
+
+    #[test]
+    fn div_by_zero_follows_ieee() {
+        let vector = Vector2f::new(1.0, -1.0) / 0.0;
+
+        assert_eq!((vector.x, vector.y), (f32::INFINITY, f32::NEG_INFINITY));
+    }
+
+    #[test]
+    fn neg_flips_each_component() {
+        assert_close(-Vector2f::new(2.0, -3.0), Vector2f::new(-2.0, 3.0));
+    }
+
+    #[test]
+    fn rotate_unit_x_by_quarter_turn_gives_unit_y() {
+        let rotated = Vector2f::new(1.0, 0.0).rotate(std::f32::consts::FRAC_PI_2);
+
+        assert!(rotated.x.abs() < 1e-6 && (rotated.y - 1.0).abs() < 1e-6, "{rotated:?}");
+    }
+
+    #[test]
+    fn converts_from_tuple() {
+        let vector: Vector2f = (1.0, 2.0).into();
+
+        assert_close(vector, Vector2f::new(1.0, 2.0));
+    }
+
+    #[test]
+    fn converts_from_array() {
+        assert_close(Vector2f::from([3.0, -4.0]), Vector2f::new(3.0, -4.0));
+    }
+
+    #[test]
+    fn converts_into_tuple() {
+        let (x, y): (f32, f32) = Vector2f::new(5.0, 6.0).into();
+
+        assert_eq!((x, y), (5.0, 6.0));
+    }
+
+    #[test]
+    fn clamp_magnitude_leaves_zero_vector_alone() {
+        assert_close(Vector2f::default().clamp_magnitude(5.0), Vector2f::default());
+    }
+
+    #[test]
+    fn clamp_magnitude_leaves_short_vectors_alone() {
+        assert_close(Vector2f::new(3.0, 4.0).clamp_magnitude(5.0), Vector2f::new(3.0, 4.0));
+    }
+
+    #[test]
+    fn clamp_magnitude_shortens_long_vectors_keeping_direction() {
+        assert_close(Vector2f::new(300.0, 400.0).clamp_magnitude(5.0), Vector2f::new(3.0, 4.0));
+    }
+
+    #[test]
+    fn reflect_off_horizontal_and_vertical_surfaces() {
+        let velocity = Vector2f::new(3.0, -4.0);
+
+        assert_close(velocity.reflect(&Vector2f::new(0.0, 1.0)), Vector2f::new(3.0, 4.0));
+        assert_close(velocity.reflect(&Vector2f::new(-1.0, 0.0)), Vector2f::new(-3.0, -4.0));
+    }
+
+    #[test]
+    fn reflect_off_diagonal_surface() {
+        let normal = Vector2f::new(1.0, 1.0).normalize();
+
+        assert_close(Vector2f::new(-1.0, 0.0).reflect(&normal), Vector2f::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn normalize_huge_vector_does_not_overflow() {
+        assert_close(Vector2f::new(3e19, 4e19).normalize(), Vector2f::new(0.6, 0.8));
+    }
+
+    #[test]
+    fn normalize_or_falls_back_on_non_finite_length() {
+        let fallback = Vector2f::new(1.0, 0.0);
+
+        assert_close(Vector2f::new(f32::INFINITY, 0.0).normalize_or(fallback), fallback);
+        assert_close(Vector2f::new(f32::NAN, 1.0).normalize_or(fallback), fallback);
+    }
 }
\ No newline at end of file
@@ -1,4 +1,8 @@
+pub mod config;
+pub mod events;
 pub mod server;
 pub mod message;
 pub mod game;
-pub mod geometry;
\ No newline at end of file
+pub mod room;
+pub mod geometry;
+pub mod metrics;
\ No newline at end of file
@@ -1,28 +1,197 @@
+use std::time::Duration;
 use actix::{Actor, Addr};
+use serde::Deserialize;
+use uuid::Uuid;
 use actix_web::{App, HttpRequest, HttpResponse, HttpServer, middleware, web};
 use actix_web::web::Data;
 use actix_web_actors::ws;
-use rust_game_server_practice::game::Game;
-use rust_game_server_practice::server::Session;
+use rust_game_server_practice::config::GameConfig;
+use rust_game_server_practice::events::{EventBus, Subscribe, SubscriberCounts, SUBSCRIBER_BUFFER};
+use rust_game_server_practice::message::{Encoding, Ping};
+use rust_game_server_practice::metrics;
+use rust_game_server_practice::room::{DEFAULT_ROOM, JoinRoom, Rooms, RoomStates};
+use rust_game_server_practice::server::{Session, SessionConfig};
 
-async fn ws(req: HttpRequest, stream: web::Payload, game: Data<Addr<Game>>) -> Result<HttpResponse, actix_web::Error> {
-    ws::start(Session::new(game.get_ref().clone()), &req, stream)
+#[derive(Deserialize)]
+struct RoomQuery {
+    room: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WsQuery {
+    room: Option<String>,
+    encoding: Option<Encoding>,
+    token: Option<Uuid>,
+}
+
+async fn ws(req: HttpRequest, stream: web::Payload, query: web::Query<WsQuery>, rooms: Data<Addr<Rooms>>, config: Data<GameConfig>) -> Result<HttpResponse, actix_web::Error> {
+    // Checked before joining, since a room's game keeps running until a session leaves it.
+    ws::handshake(&req)?;
+
+    let query = query.into_inner();
+    let room = query.room.unwrap_or_else(|| DEFAULT_ROOM.to_string());
+    let game = rooms.send(JoinRoom(room)).await.map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let mut session = Session::new(game, SessionConfig::from(config.get_ref()));
+
+    if let Some(encoding) = query.encoding {
+        session = session.with_encoding(encoding);
+    }
+
+    if let Some(token) = query.token {
+        session = session.with_token(token);
+    }
+
+    ws::start(session, &req, stream)
+}
+
+/// Reports whether the game actors still answer, for orchestrators' liveness checks.
+async fn health(rooms: Data<Addr<Rooms>>) -> HttpResponse {
+    match rooms.send(Ping).timeout(Duration::from_secs(1)).await {
+        Ok(response) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "ok",
+            "players": response.player_count,
+        })),
+        Err(_) => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
+/// Dumps a running room's `GameState` as JSON, read straight from the shared lock.
+async fn state(query: web::Query<RoomQuery>, states: Data<RoomStates>) -> HttpResponse {
+    let room = query.into_inner().room.unwrap_or_else(|| DEFAULT_ROOM.to_string());
+
+    let state = match states.read() {
+        Ok(states) => states.get(&room).cloned(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    let state = match state {
+        Some(state) => state,
+        None => return HttpResponse::NotFound().finish(),
+    };
+
+    let response = match state.read() {
+        Ok(state) => HttpResponse::Ok().json(&*state),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    };
+
+    response
+}
+
+/// Streams a room's broadcasts as server-sent events, one `data: <conversation>` per event.
+async fn events(query: web::Query<RoomQuery>, bus: Data<Addr<EventBus>>) -> HttpResponse {
+    let room = query.into_inner().room.unwrap_or_else(|| DEFAULT_ROOM.to_string());
+    let (sender, receiver) = tokio::sync::mpsc::channel(SUBSCRIBER_BUFFER);
+
+    bus.do_send(Subscribe { room, sender });
+
+    let stream = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|frame| (Ok::<_, actix_web::Error>(frame), receiver))
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .streaming(stream)
+}
+
+async fn prometheus() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(prometheus::TEXT_FORMAT)
+        .body(metrics::render())
+}
+
+/// `RUST_LOG` overrides the default of info, with request logs from actix-web.
+#[cfg(not(feature = "env-logger"))]
+fn init_logging() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info,actix_web=debug"));
+
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+}
+
+#[cfg(feature = "env-logger")]
+fn init_logging() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info,actix_web=debug")).init();
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let game = Game::default().start();
+    // Logging comes first so a rejected config.toml is reported.
+    init_logging();
 
-    std::env::set_var("RUST_LOG", "actix_web=debug");
-    env_logger::init();
+    let config = GameConfig::load("config.toml");
+    let states = RoomStates::default();
+    let subscribers = SubscriberCounts::default();
+    let bus = EventBus::new(subscribers.clone()).start();
+    let rooms = Rooms::new(config.clone(), states.clone(), bus.clone(), subscribers).start();
 
     HttpServer::new(move || {
         App::new()
-            .app_data(Data::new(game.clone()))
+            .app_data(Data::new(rooms.clone()))
+            .app_data(Data::new(config.clone()))
+            .app_data(Data::new(states.clone()))
+            .app_data(Data::new(bus.clone()))
             .wrap(middleware::Logger::default())
             .route("/", web::get().to(ws))
+            .route("/health", web::get().to(health))
+            .route("/state", web::get().to(state))
+            .route("/events", web::get().to(events))
+            .route("/metrics", web::get().to(prometheus))
     })
         .bind(("0.0.0.0", 1111))?
         .run()
         .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, RwLock};
+    use actix_web::test;
+    use uuid::Uuid;
+    use rust_game_server_practice::game::{GameState, Player, Team};
+    use rust_game_server_practice::game::entity::EntityKind;
+    use rust_game_server_practice::geometry::vector::Vector2f;
+
+    #[actix_web::test]
+    async fn state_endpoint_returns_the_rooms_game_state() {
+        let config = GameConfig::default();
+        let mut game_state = GameState::new(&config);
+        let id = Uuid::new_v4();
+        game_state.entities.insert(id, EntityKind::Player(Player::new(id, Team::Red, Vector2f::new(10.0, 20.0))));
+
+        let states = RoomStates::default();
+        states.write().unwrap().insert("arena".to_string(), Arc::new(RwLock::new(game_state)));
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(states))
+                .route("/state", web::get().to(state))
+        ).await;
+
+        let request = test::TestRequest::get().uri("/state?room=arena").to_request();
+        let body: GameState = test::call_and_read_body_json(&app, request).await;
+
+        assert!(matches!(body.entities.get(&id), Some(EntityKind::Player(_))));
+    }
+
+    #[actix_web::test]
+    async fn plain_http_request_does_not_start_a_room() {
+        let config = GameConfig::default();
+        let states = RoomStates::default();
+        let subscribers = SubscriberCounts::default();
+        let events = EventBus::new(subscribers.clone()).start();
+        let rooms = Rooms::new(config.clone(), states.clone(), events, subscribers).start();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(rooms))
+                .app_data(Data::new(config))
+                .route("/", web::get().to(ws))
+        ).await;
+
+        let request = test::TestRequest::get().uri("/?room=arena").to_request();
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert!(states.read().unwrap().is_empty());
+    }
+}
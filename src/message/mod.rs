@@ -1,16 +1,61 @@
-use actix::{Message, Recipient};
+use std::collections::HashMap;
+use actix::{Message, MessageResponse, Recipient};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
+use crate::game::{DamageCause, Team};
+use crate::game::weapon::Weapon;
 
-#[derive(Message)]
+/// Wire format a session talks in, chosen by the `encoding` query parameter or else by the kind of the first frame its client sends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[default]
+    Json,
+    Binary,
+}
+
+#[derive(Message, Clone)]
 #[rtype(result = "()")]
-pub struct MyMessage(pub String);
+pub struct MyMessage {
+    pub encoding: Encoding,
+    pub payload: Vec<u8>,
+}
+
+impl MyMessage {
+    /// JSON clients expect `data` as a nested JSON string, while MessagePack clients get it inline.
+    pub fn encode<T: Serialize>(kind: &str, data: &T, encoding: Encoding) -> Option<Self> {
+        let payload = match encoding {
+            Encoding::Json => Conversation::encode(kind, data)
+                .and_then(|conversation| serde_json::to_vec(&conversation))
+                .ok()?,
+            Encoding::Binary => rmp_serde::to_vec_named(&Conversation::new(kind.to_string(), data)).ok()?,
+        };
+
+        Some(Self {
+            encoding,
+            payload,
+        })
+    }
+}
+
+/// Why a game turned a session away; sent to the client as an `error` conversation.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectError {
+    ServerFull,
+    Unavailable,
+}
 
 #[derive(Message)]
-#[rtype(result = "()")]
+#[rtype(result = "Result<(), ConnectError>")]
 pub struct Connect {
     pub id: Uuid,
     pub addr: Recipient<MyMessage>,
+    /// Known up front when the client asked for it while connecting.
+    pub encoding: Option<Encoding>,
+    /// Reconnection token from an earlier session, whose player this one takes over if it is still waiting.
+    pub token: Option<Uuid>,
 }
 
 #[derive(Message)]
@@ -19,6 +64,23 @@ pub struct Disconnect {
     pub id: Uuid,
 }
 
+/// Liveness probe answered by `Rooms` and every `Game`.
+#[derive(Message)]
+#[rtype(result = "PingResponse")]
+pub struct Ping;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, MessageResponse)]
+pub struct PingResponse {
+    pub player_count: usize,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetEncoding {
+    pub id: Uuid,
+    pub encoding: Encoding,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Conversation<T> {
     pub kind: String,
@@ -34,6 +96,227 @@ impl<T> Conversation<T> {
     }
 }
 
+impl Conversation<String> {
+    /// Builds a conversation whose `data` is itself a JSON string, which is the shape clients parse.
+    pub fn encode<T: Serialize>(kind: &str, data: &T) -> serde_json::Result<Self> {
+        Ok(Self::new(kind.to_string(), serde_json::to_string(data)?))
+    }
+}
+
+/// Everything a client may send, encoded as `{"kind": ..., "data": ...}` like any other conversation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum ClientInput {
+    /// Thrust towards `(dx, dy)` for one tick; vectors longer than one count as full thrust.
+    Move { dx: f32, dy: f32 },
+    /// `ts` is the server `ts` of the snapshot the client was showing, used to rewind hit tests.
+    Fire {
+        x: f32,
+        y: f32,
+        #[serde(default)]
+        ts: Option<i64>,
+    },
+    Respawn,
+    Reload,
+    SwitchWeapon { weapon: Weapon },
+    SetName { name: String },
+    Chat { text: String },
+    /// Asks for a full `game_state` right away instead of waiting for the next keyframe.
+    QueryState,
+    /// Asks for the sender's own player, answered with `player_state`.
+    QuerySelf,
+    Ping { ts: i64 },
+}
+
+impl ClientInput {
+    /// The `kind` this input arrives under.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClientInput::Move { .. } => "move",
+            ClientInput::Fire { .. } => "fire",
+            ClientInput::Respawn => "respawn",
+            ClientInput::Reload => "reload",
+            ClientInput::SwitchWeapon { .. } => "switch_weapon",
+            ClientInput::SetName { .. } => "set_name",
+            ClientInput::Chat { .. } => "chat",
+            ClientInput::QueryState => "query_state",
+            ClientInput::QuerySelf => "query_self",
+            ClientInput::Ping { .. } => "ping",
+        }
+    }
+}
+
+/// A `ClientInput` as it arrives on the wire, with an optional `seq` alongside `kind` and `data`.
+/// Clients doing prediction number their inputs so `last_processed_seq` can tell them which were applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputFrame {
+    #[serde(flatten)]
+    pub input: ClientInput,
+    #[serde(default)]
+    pub seq: Option<u32>,
+}
+
 #[derive(Message)]
 #[rtype(result = "()")]
-pub struct WrappedConversation<T>(pub Uuid, pub Conversation<T>);
\ No newline at end of file
+pub struct WrappedInput(pub Uuid, pub InputFrame);
+
+/// Sent as `player_joined` and `player_left`. `name` is omitted until the player sets one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerPresence {
+    pub id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatEvent {
+    pub sender: PlayerPresence,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerDied {
+    pub victim: Uuid,
+    pub killer: Option<Uuid>,
+    pub cause: DamageCause,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillEvent {
+    pub killer: Uuid,
+    pub victim: Uuid,
+    pub kills: u32,
+    pub deaths: u32,
+}
+
+/// Human-readable account of a death, meant for display rather than game logic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillFeed {
+    pub attacker: String,
+    pub victim: String,
+    pub cause: DamageCause,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pong {
+    pub client_ts: i64,
+    pub server_ts: i64,
+}
+
+impl Pong {
+    /// Answers a client's `ping`, echoing its timestamp next to the server clock.
+    pub fn reply_to(client_ts: i64) -> Self {
+        Self {
+            client_ts,
+            server_ts: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickupEvent {
+    pub player: Uuid,
+    pub pickup: Uuid,
+    pub health: f32,
+}
+
+/// Entities that appeared, changed or disappeared since the previous snapshot a session received.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameDelta {
+    pub ts: i64,
+    pub created: HashMap<Uuid, Value>,
+    pub updated: HashMap<Uuid, Value>,
+    pub removed: Vec<Uuid>,
+    /// Highest input `seq` the server has applied for the receiving session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_processed_seq: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeedBoostEvent {
+    pub player: Uuid,
+    pub pickup: Uuid,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamAssigned {
+    pub id: Uuid,
+    pub team: Team,
+}
+
+/// Sent when a round is won, by score or by being the last team alive; `scores` is the final tally before it is reset.
+/// `winner` is empty if nobody survived.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameOver {
+    pub winner: Option<Team>,
+    pub scores: HashMap<Team, u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerScore {
+    pub id: Uuid,
+    pub name: String,
+    pub kills: u32,
+    pub deaths: u32,
+}
+
+/// Sent when the match timer runs out, with the standings just before the game resets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundOver {
+    pub winner: Option<Team>,
+    pub scores: HashMap<Team, u32>,
+    pub players: Vec<PlayerScore>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_payloads_are_rejected() {
+        for frame in [
+            r#"{"kind":"move","data":[]}"#,
+            r#"{"kind":"move","data":[1.0]}"#,
+            r#"{"kind":"fire","data":[]}"#,
+            r#"{"kind":"fire","data":{"x":1.0}}"#,
+        ] {
+            assert!(serde_json::from_str::<ClientInput>(frame).is_err(), "{frame}");
+        }
+    }
+
+    #[test]
+    fn client_inputs_round_trip() {
+        let inputs = [
+            ClientInput::Move { dx: 1.0, dy: -0.5 },
+            ClientInput::Fire { x: 10.0, y: 20.0, ts: Some(42) },
+            ClientInput::Respawn,
+        ];
+
+        for input in inputs {
+            let json = serde_json::to_string(&input).unwrap();
+            let parsed: ClientInput = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn unknown_input_kind_is_rejected() {
+        assert!(serde_json::from_str::<ClientInput>(r#"{"kind":"teleport","data":{"x":1.0}}"#).is_err());
+    }
+
+    #[test]
+    fn pong_echoes_the_ping_timestamp() {
+        let ts = match serde_json::from_str(r#"{"kind":"ping","data":{"ts":1234}}"#).unwrap() {
+            ClientInput::Ping { ts } => ts,
+            input => panic!("parsed as {input:?}"),
+        };
+
+        let before = chrono::Utc::now().timestamp_millis();
+        let pong = Pong::reply_to(ts);
+
+        assert_eq!(pong.client_ts, 1234);
+        assert!(pong.server_ts >= before);
+    }
+}
\ No newline at end of file
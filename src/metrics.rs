@@ -0,0 +1,44 @@
+use std::sync::LazyLock;
+use prometheus::{Encoder, Histogram, IntCounterVec, IntGauge, TextEncoder};
+use prometheus::{register_histogram, register_int_counter_vec, register_int_gauge};
+
+pub static SESSIONS_ACTIVE: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("game_sessions_active", "Sessions currently in a game, across all rooms")
+        .expect("game_sessions_active is registered once")
+});
+
+pub static ENTITIES_TOTAL: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("game_entities_total", "Entities alive across all rooms")
+        .expect("game_entities_total is registered once")
+});
+
+pub static TICK_DURATION_SECONDS: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "game_tick_duration_seconds",
+        "Time spent simulating and broadcasting one tick",
+        vec![0.0005, 0.001, 0.002, 0.004, 0.008, 0.016, 0.032, 0.064]
+    )
+        .expect("game_tick_duration_seconds is registered once")
+});
+
+pub static MESSAGES_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("game_messages_total", "Client inputs handled, by kind", &["kind"])
+        .expect("game_messages_total is registered once")
+});
+
+/// Everything in the default registry in the Prometheus text format.
+pub fn render() -> String {
+    // Metrics register on first use; force them so they are exported before any game has run.
+    LazyLock::force(&SESSIONS_ACTIVE);
+    LazyLock::force(&ENTITIES_TOTAL);
+    LazyLock::force(&TICK_DURATION_SECONDS);
+    LazyLock::force(&MESSAGES_TOTAL);
+
+    let mut buffer = Vec::new();
+
+    if let Err(e) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        tracing::warn!(error = %e, "failed to encode metrics");
+    }
+
+    String::from_utf8(buffer).unwrap_or_default()
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use actix::{Actor, Addr, Context, Handler, Message, ResponseFuture};
+use futures_util::future::join_all;
+use crate::config::GameConfig;
+use crate::events::{EventBus, SubscriberCounts};
+use crate::game::{Game, GameState};
+use crate::message::{Ping, PingResponse};
+
+/// Room joined when a client does not ask for one.
+pub const DEFAULT_ROOM: &str = "default";
+
+#[derive(Message)]
+#[rtype(result = "Addr<Game>")]
+pub struct JoinRoom(pub String);
+
+/// State of every running room, shared with HTTP handlers so they can read it without messaging the actors.
+pub type RoomStates = Arc<RwLock<HashMap<String, Arc<RwLock<GameState>>>>>;
+
+/// Maps room ids to their own `Game`, starting one the first time a room is joined.
+pub struct Rooms {
+    config: GameConfig,
+    games: HashMap<String, Addr<Game>>,
+    states: RoomStates,
+    events: Addr<EventBus>,
+    subscribers: SubscriberCounts,
+}
+
+impl Rooms {
+    pub fn new(config: GameConfig, states: RoomStates, events: Addr<EventBus>, subscribers: SubscriberCounts) -> Self {
+        Self {
+            config,
+            games: HashMap::new(),
+            states,
+            events,
+            subscribers,
+        }
+    }
+}
+
+impl Actor for Rooms {
+    type Context = Context<Self>;
+}
+
+impl Handler<JoinRoom> for Rooms {
+    type Result = Addr<Game>;
+
+    fn handle(&mut self, msg: JoinRoom, _ctx: &mut Self::Context) -> Self::Result {
+        // Games stop once their last session leaves, so a dead address means the room has to start over.
+        self.games.retain(|_, game| game.connected());
+
+        let mut states = match self.states.write() {
+            Ok(states) => states,
+            Err(e) => e.into_inner(),
+        };
+
+        states.retain(|room, _| self.games.contains_key(room));
+
+        self.games
+            .entry(msg.0.clone())
+            .or_insert_with(|| {
+                let game = Game::new(self.config.clone()).with_events(self.events.clone(), self.subscribers.clone(), msg.0.clone());
+                states.insert(msg.0, game.state());
+                game.start()
+            })
+            .clone()
+    }
+}
+
+impl Handler<Ping> for Rooms {
+    type Result = ResponseFuture<PingResponse>;
+
+    /// Waits on every running game, so one stuck game makes the whole probe hang.
+    fn handle(&mut self, _msg: Ping, _ctx: &mut Self::Context) -> Self::Result {
+        let pings: Vec<_> = self.games
+            .values()
+            .filter(|game| game.connected())
+            .map(|game| game.send(Ping))
+            .collect();
+
+        Box::pin(async move {
+            let player_count = join_all(pings)
+                .await
+                .into_iter()
+                .filter_map(Result::ok)
+                .map(|response| response.player_count)
+                .sum();
+
+            PingResponse { player_count }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use uuid::Uuid;
+    use crate::message::{Connect, MyMessage};
+
+    /// Keeps the raw payload of everything a session is sent.
+    struct Recorder(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    impl Actor for Recorder {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<MyMessage> for Recorder {
+        type Result = ();
+
+        fn handle(&mut self, msg: MyMessage, _ctx: &mut Self::Context) -> Self::Result {
+            self.0.lock().unwrap().push(msg.payload);
+        }
+    }
+
+    async fn join(rooms: &Addr<Rooms>, room: &str) -> (Uuid, Arc<Mutex<Vec<Vec<u8>>>>) {
+        let game = rooms.send(JoinRoom(room.to_string())).await.unwrap();
+        let received = Arc::default();
+        let addr = Recorder(Arc::clone(&received)).start();
+        let id = Uuid::new_v4();
+
+        game.send(Connect { id, addr: addr.recipient(), encoding: None, token: None }).await.unwrap().unwrap();
+
+        (id, received)
+    }
+
+    #[actix::test]
+    async fn rooms_do_not_see_each_others_players() {
+        // The default view is unlimited, so anything shared between the rooms would show up in the broadcasts.
+        let subscribers = SubscriberCounts::default();
+        let events = EventBus::new(subscribers.clone()).start();
+        let rooms = Rooms::new(GameConfig::default(), RoomStates::default(), events, subscribers).start();
+
+        let (first, _) = join(&rooms, "a").await;
+        let (_, received) = join(&rooms, "b").await;
+        actix::clock::sleep(Duration::from_millis(100)).await;
+
+        let first = first.to_string();
+        let received = received.lock().unwrap();
+        assert!(!received.is_empty());
+        assert!(received.iter().all(|payload| !String::from_utf8_lossy(payload).contains(&first)));
+    }
+
+    #[actix::test]
+    async fn ping_counts_players_across_rooms() {
+        let subscribers = SubscriberCounts::default();
+        let events = EventBus::new(subscribers.clone()).start();
+        let rooms = Rooms::new(GameConfig::default(), RoomStates::default(), events, subscribers).start();
+
+        join(&rooms, "a").await;
+        join(&rooms, "a").await;
+        join(&rooms, "b").await;
+
+        assert_eq!(rooms.send(Ping).await.unwrap().player_count, 3);
+    }
+}
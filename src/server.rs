@@ -1,28 +1,156 @@
-use std::time::{Duration, Instant};
+use std::time::Duration;
+use actix::clock::Instant;
 use actix::prelude::*;
 use actix_web_actors::ws;
 use uuid::Uuid;
+use crate::config::GameConfig;
 use crate::game::Game;
-use crate::message::{Connect, Conversation, Disconnect, MyMessage, WrappedConversation};
+use crate::message::{ClientInput, Connect, Disconnect, Encoding, MyMessage, InputFrame, Pong, SetEncoding, WrappedInput};
+use crate::metrics;
+
+/// Per-connection settings taken from `GameConfig`. `heartbeat_timeout` must exceed `heartbeat_interval`,
+/// otherwise a healthy client can be dropped before it is even pinged; `GameConfig::validate` enforces this.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionConfig {
+    pub heartbeat_timeout: Duration,
+    pub heartbeat_interval: Duration,
+    /// Messages regained per second.
+    pub message_rate: f32,
+    /// Most messages a session may send back to back.
+    pub message_burst: f32,
+}
+
+impl From<&GameConfig> for SessionConfig {
+    fn from(config: &GameConfig) -> Self {
+        Self {
+            heartbeat_timeout: Duration::from_secs(config.heartbeat_timeout_secs),
+            heartbeat_interval: Duration::from_secs(config.heartbeat_interval_secs),
+            message_rate: config.message_rate,
+            message_burst: config.message_burst,
+        }
+    }
+}
 
 pub struct Session {
     id: Uuid,
     bz: Instant,
+    config: SessionConfig,
+    last_message_time: Instant,
+    tokens: f32,
+    /// Set once the client has been told it is rate limited, until a message gets through again.
+    rate_limited: bool,
+    encoding: Option<Encoding>,
+    token: Option<Uuid>,
+    /// Parent of every event about this session, so logs can be correlated by its id.
+    span: tracing::Span,
     addr: Addr<Game>,
 }
 
 impl Session {
-    pub fn new(game: Addr<Game>) -> Self {
+    pub fn new(game: Addr<Game>, config: SessionConfig) -> Self {
+        let id = Uuid::new_v4();
+
         Self {
-            id: Uuid::new_v4(),
+            id,
             bz: Instant::now(),
+            config,
+            last_message_time: Instant::now(),
+            tokens: config.message_burst,
+            rate_limited: false,
+            encoding: None,
+            token: None,
+            span: tracing::info_span!("session", id = %id),
             addr: game
         }
     }
 
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Reclaims the player of an earlier session that was handed `token`.
+    pub fn with_token(mut self, token: Uuid) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Refills the token bucket for the time since the last message and spends a token if one is left.
+    fn allow_message(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_message_time).as_secs_f32();
+        self.last_message_time = now;
+
+        self.tokens = (self.tokens + elapsed * self.config.message_rate).min(self.config.message_burst);
+
+        if self.tokens < 1.0 {
+            return false;
+        }
+
+        self.tokens -= 1.0;
+        true
+    }
+
+    /// Decodes a client frame and forwards it to the game, adopting the frame's encoding if it is the first one.
+    fn receive(&mut self, encoding: Encoding, frame: &[u8], ctx: &mut <Self as Actor>::Context) {
+        if !self.allow_message() {
+            if !self.rate_limited {
+                self.rate_limited = true;
+                tracing::warn!(parent: &self.span, "rate limited");
+
+                if let Some(msg) = MyMessage::encode("error", &"rate_limited", self.encoding.unwrap_or(encoding)) {
+                    Self::write(msg, ctx);
+                }
+            }
+
+            return;
+        }
+
+        self.rate_limited = false;
+
+        if self.encoding.is_none() {
+            self.encoding = Some(encoding);
+            self.addr.do_send(SetEncoding {
+                id: self.id,
+                encoding,
+            });
+        }
+
+        let input = match encoding {
+            Encoding::Json => serde_json::from_slice::<InputFrame>(frame).map_err(|e| e.to_string()),
+            Encoding::Binary => rmp_serde::from_slice::<InputFrame>(frame).map_err(|e| e.to_string()),
+        };
+
+        if let Ok(frame) = &input {
+            metrics::MESSAGES_TOTAL.with_label_values(&[frame.input.kind()]).inc();
+        }
+
+        match input {
+            // Answered here rather than by the game, so the round trip doesn't include time queued in its mailbox.
+            Ok(InputFrame { input: ClientInput::Ping { ts }, .. }) => {
+                if let Some(msg) = MyMessage::encode("pong", &Pong::reply_to(ts), self.encoding.unwrap_or(encoding)) {
+                    Self::write(msg, ctx);
+                }
+            }
+            Ok(input) => self.addr.do_send(WrappedInput(self.id, input)),
+            Err(e) => tracing::warn!(parent: &self.span, error = %e, "malformed message"),
+        }
+    }
+
+    fn write(msg: MyMessage, ctx: &mut <Self as Actor>::Context) {
+        match msg.encoding {
+            Encoding::Json => match String::from_utf8(msg.payload) {
+                Ok(text) => ctx.text(text),
+                Err(e) => tracing::warn!(error = %e, "dropped non-UTF-8 text frame"),
+            },
+            Encoding::Binary => ctx.binary(msg.payload),
+        }
+    }
+
     fn bz(&self, ctx: &mut <Self as Actor>::Context) {
-        ctx.run_interval(Duration::from_secs(5), |act, ctx| {
-            if Instant::now().duration_since(act.bz) > Duration::from_secs(10) {
+        ctx.run_interval(self.config.heartbeat_interval, |act, ctx| {
+            if Instant::now().duration_since(act.bz) > act.config.heartbeat_timeout {
+                tracing::info!(parent: &act.span, "heartbeat timed out");
                 ctx.stop();
                 return;
             }
@@ -36,6 +164,8 @@ impl Actor for Session {
     type Context = ws::WebsocketContext<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
+        tracing::info!(parent: &self.span, "connected");
+
         self.bz(ctx);
 
         let addr = ctx.address();
@@ -43,11 +173,26 @@ impl Actor for Session {
         self.addr.send(Connect {
             id: self.id,
             addr: addr.recipient(),
+            encoding: self.encoding,
+            token: self.token,
         })
             .into_actor(self)
-            .then(|res, _act, ctx| {
+            .then(|res, act, ctx| {
                 match res {
-                    Ok(_) => {},
+                    Ok(Ok(())) => {},
+                    Ok(Err(e)) => {
+                        tracing::info!(parent: &act.span, reason = ?e, "rejected by game");
+
+                        if let Some(msg) = MyMessage::encode("error", &e, act.encoding.unwrap_or_default()) {
+                            Self::write(msg, ctx);
+                        }
+
+                        ctx.close(Some(ws::CloseReason {
+                            code: ws::CloseCode::Again,
+                            description: None,
+                        }));
+                        ctx.stop();
+                    }
                     _ => ctx.stop()
                 }
                 fut::ready(())
@@ -57,6 +202,8 @@ impl Actor for Session {
     }
 
     fn stopped(&mut self, _ctx: &mut Self::Context) {
+        tracing::info!(parent: &self.span, "disconnected");
+
         self.addr.do_send(Disconnect {
             id: self.id,
         });
@@ -78,15 +225,10 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Session {
                 ctx.stop();
             }
             Ok(ws::Message::Text(s)) => {
-                let conversation = match serde_json::from_str::<Conversation<Vec<f32>>>(&s) {
-                    Ok(conversation) => conversation,
-                    Err(e) => {
-                        println!("Error: {}", e);
-                        return;
-                    }
-                };
-
-                self.addr.do_send(WrappedConversation(self.id, conversation));
+                self.receive(Encoding::Json, s.as_bytes(), ctx);
+            }
+            Ok(ws::Message::Binary(b)) => {
+                self.receive(Encoding::Binary, &b, ctx);
             }
             _ => {}
         }
@@ -97,6 +239,60 @@ impl Handler<MyMessage> for Session {
     type Result = ();
 
     fn handle(&mut self, msg: MyMessage, ctx: &mut Self::Context) {
-        ctx.text(msg.0);
+        Self::write(msg, ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use crate::message::Ping;
+
+    fn session(message_rate: f32, message_burst: f32) -> Session {
+        let config = SessionConfig { message_rate, message_burst, ..SessionConfig::from(&GameConfig::default()) };
+        Session::new(Game::new(GameConfig::default()).start(), config)
+    }
+
+    #[actix::test]
+    async fn burst_beyond_the_limit_is_not_forwarded() {
+        let mut session = session(1.0, 5.0);
+
+        let forwarded = (0..20).filter(|_| session.allow_message()).count();
+
+        assert_eq!(forwarded, 5);
+    }
+
+    #[actix::test]
+    async fn limiter_refills_over_time() {
+        let mut session = session(10.0, 5.0);
+        while session.allow_message() {}
+
+        session.last_message_time -= Duration::from_millis(300);
+
+        let forwarded = (0..20).filter(|_| session.allow_message()).count();
+        assert_eq!(forwarded, 3);
+    }
+
+    #[actix::test]
+    async fn silent_client_is_dropped_after_the_heartbeat_timeout() {
+        // Paused time jumps ahead whenever nothing else is ready, so the heartbeat runs out straight away.
+        tokio::time::pause();
+        let game = Game::new(GameConfig::default()).start();
+        let config = SessionConfig::from(&GameConfig::default());
+        let silence = futures_util::stream::pending::<Result<actix_web::web::Bytes, actix_web::error::PayloadError>>();
+        let mut frames = Box::pin(ws::WebsocketContext::create(Session::new(game.clone(), config), silence));
+
+        let connected = Instant::now();
+
+        // The frames only end once the session has stopped.
+        let closed = actix::clock::timeout(config.heartbeat_timeout * 3, async {
+            while frames.next().await.is_some() {}
+        })
+            .await;
+
+        assert!(closed.is_ok());
+        assert!(connected.elapsed() > config.heartbeat_timeout);
+        assert_eq!(game.send(Ping).await.unwrap().player_count, 0);
     }
 }
\ No newline at end of file
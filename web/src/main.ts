@@ -54,7 +54,18 @@ class Provider {
 
   async connect() {
     return new Promise<void>((resolve, reject) => {
-      this.socket = new WebSocket('ws://localhost:1111/');
+      const params = new URLSearchParams();
+      const room = new URLSearchParams(window.location.search).get('room');
+      if (room) {
+        params.set('room', room);
+      }
+
+      const token = sessionStorage.getItem('reconnect_token');
+      if (token) {
+        params.set('token', token);
+      }
+
+      this.socket = new WebSocket(`ws://localhost:1111/?${params}`);
       this.socket.onopen = () => {
         resolve();
       }
@@ -129,7 +140,7 @@ class NetworkedEntity extends Entity {
 class Bullet extends NetworkedEntity {
   targetPosition: Vector2f;
 
-  constructor(public position: Vector2f) {
+  constructor(public position: Vector2f, public radius: number) {
     super();
 
     this.targetPosition = this.position.clone();
@@ -149,7 +160,7 @@ class Bullet extends NetworkedEntity {
   render(ctx: CanvasRenderingContext2D) {
     ctx.beginPath();
     ctx.fillStyle = 'red';
-    ctx.arc(this.position.x, this.position.y, 5, 0, Math.PI * 2);
+    ctx.arc(this.position.x, this.position.y, this.radius, 0, Math.PI * 2);
     ctx.fill();
   }
 
@@ -161,7 +172,7 @@ class Bullet extends NetworkedEntity {
 class Player extends NetworkedEntity {
   targetPosition: Vector2f;
 
-  constructor(public position: Vector2f) {
+  constructor(public position: Vector2f, public radius: number, public name: string, public team: string) {
     super();
 
     this.targetPosition = this.position.clone();
@@ -180,13 +191,66 @@ class Player extends NetworkedEntity {
 
   render(ctx: CanvasRenderingContext2D) {
     ctx.beginPath();
-    ctx.fillStyle = '#333';
-    ctx.arc(this.position.x, this.position.y, 10, 0, Math.PI * 2);
+    ctx.fillStyle = this.team === 'blue' ? '#36c' : '#c33';
+    ctx.arc(this.position.x, this.position.y, this.radius, 0, Math.PI * 2);
     ctx.fill();
+
+    if (this.name) {
+      ctx.textAlign = 'center';
+      ctx.fillText(this.name, this.position.x, this.position.y - 16);
+    }
   }
 
   updateData(data: Player) {
     this.targetPosition = new Vector2f(data.position.x, data.position.y);
+    this.name = data.name;
+    this.team = data.team;
+  }
+}
+
+class Wall extends NetworkedEntity {
+  constructor(public position: Vector2f, public size: Vector2f) {
+    super();
+  }
+
+  render(ctx: CanvasRenderingContext2D) {
+    ctx.fillStyle = '#888';
+    ctx.fillRect(this.position.x, this.position.y, this.size.x, this.size.y);
+  }
+}
+
+class HealthPack extends NetworkedEntity {
+  constructor(public position: Vector2f) {
+    super();
+  }
+
+  render(ctx: CanvasRenderingContext2D) {
+    ctx.fillStyle = '#2a2';
+    ctx.fillRect(this.position.x - 6, this.position.y - 6, 12, 12);
+  }
+}
+
+class SpeedBoost extends NetworkedEntity {
+  constructor(public position: Vector2f) {
+    super();
+  }
+
+  render(ctx: CanvasRenderingContext2D) {
+    ctx.fillStyle = '#28f';
+    ctx.fillRect(this.position.x - 6, this.position.y - 6, 12, 12);
+  }
+}
+
+class Explosion extends NetworkedEntity {
+  constructor(public position: Vector2f, public radius: number) {
+    super();
+  }
+
+  render(ctx: CanvasRenderingContext2D) {
+    ctx.beginPath();
+    ctx.fillStyle = 'rgba(255, 140, 0, 0.4)';
+    ctx.arc(this.position.x, this.position.y, this.radius, 0, Math.PI * 2);
+    ctx.fill();
   }
 }
 
@@ -237,16 +301,50 @@ class EntityManager {
         return this.add(
           id,
           new Player(
-            new Vector2f(data.position.x, data.position.y)
+            new Vector2f(data.position.x, data.position.y),
+            data.radius,
+            data.name,
+            data.team
           )
         );
       case 'Bullet':
         return this.add(
           id,
           new Bullet(
+            new Vector2f(data.position.x, data.position.y),
+            data.radius
+          )
+        );
+      case 'Wall':
+        return this.add(
+          id,
+          new Wall(
+            new Vector2f(data.position.x, data.position.y),
+            new Vector2f(data.size.x, data.size.y)
+          )
+        );
+      case 'HealthPack':
+        return this.add(
+          id,
+          new HealthPack(
+            new Vector2f(data.position.x, data.position.y)
+          )
+        );
+      case 'SpeedBoost':
+        return this.add(
+          id,
+          new SpeedBoost(
             new Vector2f(data.position.x, data.position.y)
           )
         );
+      case 'Explosion':
+        return this.add(
+          id,
+          new Explosion(
+            new Vector2f(data.position.x, data.position.y),
+            data.radius
+          )
+        );
       default:
         return;
     }
@@ -272,6 +370,7 @@ class Game {
 
   count = 0;
   sumLatency = 0;
+  lastServerTs?: number;
 
   constructor(canvas: HTMLCanvasElement) {
     this.context = canvas.getContext('2d')!;
@@ -280,6 +379,15 @@ class Game {
   async start() {
     await this.provider.connect();
 
+    this.provider.on('reconnect_token', (token: string) => {
+      sessionStorage.setItem('reconnect_token', token);
+    });
+
+    const name = new URLSearchParams(window.location.search).get('name');
+    if (name) {
+      this.provider.send('set_name', { name });
+    }
+
     this.provider.on('game_state', (data: { ts: number, entities: { [key: string]: Entity } }) => {
       const addedChanges = addedDiff(this.data.entities, data.entities);
       const updatedChanges = updatedDiff(this.data.entities, data.entities);
@@ -299,19 +407,48 @@ class Game {
         this.entityManager.remove(key);
       }
 
-      const latency = Date.now() - data.ts;
-      this.sumLatency += latency;
-      this.count++;
+      this.updateLatency(data.ts);
+    });
 
-      paper.textContent = `Latency: ${latency}ms
-Average Latency: ${this.sumLatency / this.count}ms`;
+    this.provider.on('game_delta', (data: { ts: number, created: { [key: string]: Entity }, updated: { [key: string]: Entity }, removed: string[] }) => {
+      for (const key in data.created) {
+        this.data.entities[key] = data.created[key];
+        this.entityManager.addNetworkedEntity(key, data.created[key]);
+      }
+
+      for (const key in data.updated) {
+        this.data.entities[key] = data.updated[key];
+        this.entityManager.updateNetworkedEntity(key, data.updated[key]);
+      }
+
+      for (const key of data.removed) {
+        delete this.data.entities[key];
+        this.entityManager.remove(key);
+      }
+
+      this.updateLatency(data.ts);
     });
 
     this.startTime = Date.now();
     window.requestAnimationFrame(this.enterFrame);
 
+    const weapons: { [key: string]: object } = {
+      '1': { kind: 'pistol' },
+      '2': { kind: 'shotgun', pellet_count: 5 },
+      '3': { kind: 'sniper' },
+      '4': { kind: 'grenade' },
+    };
+
     window.addEventListener('keydown', (e) => {
       this.keyMap[e.key] = true;
+
+      if (e.key === 'r') {
+        this.provider.send('reload');
+      }
+
+      if (weapons[e.key]) {
+        this.provider.send('switch_weapon', { weapon: weapons[e.key] });
+      }
     });
 
     window.addEventListener('keyup', (e) => {
@@ -326,10 +463,20 @@ Average Latency: ${this.sumLatency / this.count}ms`;
       x -= rect.left;
       y -= rect.top;
 
-      this.provider.send('fire', [x, y]);
+      this.provider.send('fire', { x, y, ts: this.lastServerTs });
     });
   }
 
+  updateLatency(ts: number) {
+    this.lastServerTs = ts;
+    const latency = Date.now() - ts;
+    this.sumLatency += latency;
+    this.count++;
+
+    paper.textContent = `Latency: ${latency}ms
+Average Latency: ${this.sumLatency / this.count}ms`;
+  }
+
   enterFrame = () => {
     const currentTime = Date.now();
     const delta = (currentTime - this.startTime) * 0.001;
@@ -361,7 +508,7 @@ Average Latency: ${this.sumLatency / this.count}ms`;
     }
 
     if (pos[0] !== 0 || pos[1] !== 0) {
-      this.provider.send('move', pos);
+      this.provider.send('move', { dx: pos[0], dy: pos[1] });
     }
 
     window.requestAnimationFrame(this.enterFrame);